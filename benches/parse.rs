@@ -0,0 +1,38 @@
+use search_query_parser::{Approach, Parser};
+use std::hint::black_box;
+use std::time::Instant;
+
+// `cargo bench`, the time per query of each approach with a shared parser
+const ITERATIONS: usize = 2000;
+
+const QUERIES: [&str; 5] = [
+    "rust",
+    "rust OR golang AND -java",
+    "title:\"search query\" -author:bob (parser OR lexer)",
+    "（Ａ１　ＯＲ　Ａ２）　ＡＮＤ　-”Ｐ１　Ｐ２”　title^2:(Ａ３ -Ａ４)",
+    "A OR B) AND (C \"\" () AND OR -(D OR (E F (G OR \"H I\")))",
+];
+
+fn bench(name: &str, parse: impl Fn(&str)) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        QUERIES.iter().for_each(|query| parse(black_box(query)))
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:.1} µs/query",
+        name,
+        elapsed.as_secs_f64() * 1e6 / (ITERATIONS * QUERIES.len()) as f64
+    )
+}
+
+fn main() {
+    [Approach::Regex, Approach::Bnf]
+        .into_iter()
+        .for_each(|approach| {
+            let parser = Parser::new().with_approach(approach);
+            bench(&format!("{:?}", approach), |query| {
+                black_box(parser.parse(query).unwrap());
+            })
+        });
+}
@@ -1 +1,68 @@
+//! Grammar driven parser, an alternative to the regex approach which doesn't rewrite the query with placeholders.
+//!
+//! ```text
+//! query    ::= or_expr
+//! or_expr  ::= and_expr { OR and_expr }
+//! and_expr ::= near_expr { [ AND ] near_expr }
+//! near_expr ::= unary { NEAR unary }
+//! unary    ::= keyword
+//!            | NOT unary
+//!            | [ "-" | "!" | "+" ] [ target ":" ] phrase
+//!            | [ "-" | "!" | "+" ] [ target ":" ] group
+//! group    ::= "(" or_expr ")" [ boost ]
+//! phrase   ::= '"' { any character except '"' | escaped } '"' [ "~" { digit } ] [ boost ]
+//! keyword  ::= [ "-" | "!" | "+" ] word
+//!            | [ "-" | "!" | "+" ] [ target ":" ] regex [ boost ]
+//! regex    ::= "/" { any character except "/" | "\/" | escaped } "/"   (validated by the regex crate)
+//! target   ::= word
+//! word     ::= { any character except space, '"', "(" and ")" | escaped }
+//! escaped  ::= "\" any character
+//! boost    ::= "^" digit { digit } [ "." digit { digit } ]   (a word ending with it is boosted too, e.g. `rust^2`)
+//! AND      ::= "AND" | "&&" | "&"   (case insensitive, half or full width, the symbols can be disabled)
+//! OR       ::= "OR" | "||" | "|"    (case insensitive, half or full width, the symbols can be disabled)
+//! NOT      ::= "NOT"                (case insensitive, half or full width)
+//! NEAR     ::= [ "O" ] "NEAR/" digit { digit }   (case insensitive, half or full width, "ONEAR" is in order)
+//! ```
+//!
+//! full width space, brackets and double quotation are read as the half width ones,
+//! and incorrect queries are corrected the same way as the regex approach:
+//! double quotations and brackets without pair are ignored, empty phrases and brackets are removed,
+//! and AND / OR without keywords on both sides are ignored.
+//!
+//! NEAR has higher priority than AND, and AND has higher priority than OR,
+//! e.g. `A OR B C NEAR/5 D` is parsed as `A OR (B AND (C NEAR/5 D))`, and `A NEAR/3 B NEAR/5 C` as `(A NEAR/3 B) NEAR/5 C`.
+//! the priorities are kept across brackets too, e.g. `A OR B AND (C)` is parsed as `A OR (B AND C)`,
+//! the same as the regex approach.
 
+use crate::bnf_approach::parser::ConditionParser;
+use crate::bnf_approach::token::Tokenizer;
+use crate::{Condition, SpannedCondition, SyntaxError};
+
+mod parser;
+mod spanned_condition;
+mod token;
+
+// `+A` and the groups are kept as `Condition::Boolean` for the default operator of the parser
+pub(crate) fn parse(
+    query: &str, symbolic_operators: bool, exists_alias: Option<&str>,
+) -> Condition {
+    parse_with_errors(query, symbolic_operators, exists_alias)
+        .0
+        .condition()
+}
+
+// the errors of the tokenizer and the parser are sorted together by the position in the query,
+// so the first error is the leftmost problem
+pub(crate) fn parse_with_errors(
+    query: &str, symbolic_operators: bool, exists_alias: Option<&str>,
+) -> (SpannedCondition, Vec<SyntaxError>) {
+    let (tokens, mut errors) = Tokenizer::new()
+        .with_symbolic_operators(symbolic_operators)
+        .tokenize(query);
+    let (condition, parse_errors) = ConditionParser::new(tokens)
+        .with_exists_alias(exists_alias)
+        .parse();
+    errors.extend(parse_errors);
+    errors.sort_by_key(|error| error.span().start);
+    (condition, errors)
+}
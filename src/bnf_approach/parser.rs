@@ -0,0 +1,585 @@
+use crate::bnf_approach::spanned_condition::cover;
+use crate::bnf_approach::token::Token;
+use crate::boost::boost;
+use crate::escape::unescape;
+use crate::term::{is_invalid_regex, is_too_many_edits, phrase_condition, term_condition};
+use crate::{Operator, Span, SpannedCondition, SyntaxError};
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+pub(crate) struct ConditionParser {
+    tokens: Peekable<IntoIter<(Token, Span)>>,
+    // where the parser is, for the span of the empty conditions
+    position: usize,
+    errors: Vec<SyntaxError>,
+    // `has:title` is `_exists_:title` with the alias `has`
+    exists_alias: Option<String>,
+}
+
+impl ConditionParser {
+    pub(crate) fn new(tokens: Vec<(Token, Span)>) -> Self {
+        Self {
+            tokens: tokens.into_iter().peekable(),
+            position: 0,
+            errors: vec![],
+            exists_alias: None,
+        }
+    }
+
+    pub(crate) fn with_exists_alias(mut self, exists_alias: Option<&str>) -> Self {
+        self.exists_alias = exists_alias.map(String::from);
+        self
+    }
+
+    pub(crate) fn parse(mut self) -> (SpannedCondition, Vec<SyntaxError>) {
+        let mut conditions = vec![self.parse_or()];
+        // a closing bracket is always paired by the tokenizer, this only keeps the parser total
+        while self.next().is_some() {
+            conditions.push(self.parse_or())
+        }
+        (
+            self.operator(Operator::And, conditions).simplify(),
+            self.errors,
+        )
+    }
+
+    // or_expr ::= and_expr { OR and_expr }
+    fn parse_or(&mut self) -> SpannedCondition {
+        let mut conditions = vec![self.parse_and()];
+        while let Some((_, span)) = self.next_if_eq(&Token::Or) {
+            let condition = self.parse_and();
+            // `OR` without keyword on the left or the right side is ignored
+            if conditions.iter().all(is_empty) || is_empty(&condition) {
+                self.errors.push(SyntaxError::DanglingOperator(span))
+            }
+            conditions.push(condition)
+        }
+        self.operator(Operator::Or, conditions)
+    }
+
+    // and_expr ::= near_expr { [ AND ] near_expr }, the conditions on both sides of `AND` are required
+    fn parse_and(&mut self) -> SpannedCondition {
+        let mut conditions = Vec::<SpannedCondition>::new();
+        let mut required = false;
+        loop {
+            match self.tokens.peek() {
+                None | Some((Token::Or, _)) | Some((Token::Close, _)) => break,
+                Some((Token::And, _)) => {
+                    let span = self.next().map(|(_, span)| span).unwrap_or_default();
+                    // `AND` without keyword on the left or the right side is ignored
+                    let has_right = !matches!(
+                        self.tokens.peek(),
+                        None | Some((Token::Or | Token::Close | Token::And | Token::Near(_), _))
+                    );
+                    match conditions.pop() {
+                        Some(left) if has_right => {
+                            conditions.push(left.required_by_and());
+                            required = true
+                        }
+                        left => {
+                            conditions.extend(left);
+                            self.errors.push(SyntaxError::DanglingOperator(span))
+                        }
+                    }
+                }
+                // `NEAR/n` without keyword on the left side is ignored
+                Some((Token::Near(_), _)) => {
+                    let span = self.next().map(|(_, span)| span).unwrap_or_default();
+                    self.errors.push(SyntaxError::DanglingOperator(span))
+                }
+                Some(_) => {
+                    let condition = self.parse_near();
+                    conditions.push(match required {
+                        true => condition.required_by_and(),
+                        false => condition,
+                    });
+                    required = false
+                }
+            }
+        }
+        self.operator(Operator::And, conditions)
+    }
+
+    // near_expr ::= unary { NEAR unary }, `A NEAR/3 B NEAR/5 C` is `(A NEAR/3 B) NEAR/5 C`
+    fn parse_near(&mut self) -> SpannedCondition {
+        let mut condition = self.parse_unary();
+        while let Some((Token::Near(operator), span)) = self
+            .tokens
+            .next_if(|(token, _)| matches!(token, Token::Near(_)))
+        {
+            self.position = span.end;
+            // `NEAR/n` without keyword on the right side is ignored
+            match self.tokens.peek() {
+                None | Some((Token::And | Token::Or | Token::Close | Token::Near(_), _)) => {
+                    self.errors.push(SyntaxError::DanglingOperator(span))
+                }
+                _ => {
+                    let right = self.parse_unary();
+                    condition = self.operator(operator, vec![condition, right])
+                }
+            }
+        }
+        condition
+    }
+
+    // unary ::= keyword | NOT unary | [ "-" | "+" ] [ target ":" ] phrase | [ "-" | "+" ] [ target ":" ] group
+    fn parse_unary(&mut self) -> SpannedCondition {
+        match self.next() {
+            Some((Token::Word(word), span)) => self.keyword_condition(word, span),
+            Some((Token::Phrase(phrase, slop), span)) => {
+                let condition =
+                    SpannedCondition::leaf(phrase_condition(phrase, slop.as_deref()), span);
+                self.boosted(condition)
+            }
+            Some((Token::Minus, span)) => match self.tokens.peek() {
+                // `NOT` without keyword on the right side is ignored
+                None | Some((Token::And | Token::Or | Token::Close, _)) => {
+                    self.errors.push(SyntaxError::DanglingOperator(span));
+                    SpannedCondition::none(span)
+                }
+                _ => {
+                    let condition = self.parse_unary();
+                    let span = Span {
+                        start: span.start,
+                        end: condition.span.end,
+                    };
+                    SpannedCondition::not(condition, span)
+                }
+            },
+            Some((Token::Plus, span)) => {
+                let condition = self.parse_unary();
+                let span = Span {
+                    start: span.start,
+                    end: condition.span.end,
+                };
+                SpannedCondition::required(condition, span)
+            }
+            Some((Token::Target(target), span)) => {
+                let condition = self.parse_unary();
+                let span = Span {
+                    start: span.start,
+                    end: condition.span.end,
+                };
+                SpannedCondition::targeted(unescape(&target, &[]), condition, span)
+            }
+            Some((Token::Open, span)) => {
+                let mut condition = self.parse_or();
+                let end = self
+                    .next_if_eq(&Token::Close)
+                    .map_or(condition.span.end, |(_, close)| close.end);
+                // the group covers its brackets
+                condition.span = Span {
+                    start: span.start,
+                    end,
+                };
+                let condition = SpannedCondition::group(condition);
+                self.boosted(condition)
+            }
+            _ => SpannedCondition::none(self.empty_span()),
+        }
+    }
+
+    // `"phrase"^2` and `(a OR b)^2`, the boost is the word just after the phrase or the brackets
+    fn boosted(&mut self, condition: SpannedCondition) -> SpannedCondition {
+        let boost = match self.tokens.peek() {
+            Some((Token::Word(word), span)) if span.start == condition.span.end => {
+                boost(word).map(|boost| (boost, span.end))
+            }
+            _ => None,
+        };
+        match boost {
+            Some((boost, end)) => {
+                self.next();
+                let span = Span {
+                    start: condition.span.start,
+                    end,
+                };
+                SpannedCondition::boosted(condition, boost, span)
+            }
+            None => condition,
+        }
+    }
+
+    fn next(&mut self) -> Option<(Token, Span)> {
+        let next = self.tokens.next();
+        if let Some((_, span)) = &next {
+            self.position = span.end
+        }
+        next
+    }
+
+    fn next_if_eq(&mut self, token: &Token) -> Option<(Token, Span)> {
+        match self.tokens.peek() {
+            Some((next, _)) if next == token => self.next(),
+            _ => None,
+        }
+    }
+
+    fn operator(&self, operator: Operator, conditions: Vec<SpannedCondition>) -> SpannedCondition {
+        let span = cover(
+            conditions
+                .iter()
+                .filter(|condition| !condition.is_none())
+                .map(|condition| condition.span),
+        )
+        .unwrap_or_else(|| self.empty_span());
+        SpannedCondition::operator(operator, conditions, span)
+    }
+
+    fn keyword_condition(&mut self, word: String, span: Span) -> SpannedCondition {
+        let child_span = Span {
+            start: span.start + 1,
+            end: span.end,
+        };
+        match (word.len(), word.strip_prefix('-'), word.strip_prefix('+')) {
+            (1, _, _) | (_, None, None) => self.term(&word, span),
+            (_, Some(keyword), _) => SpannedCondition::not(self.term(keyword, child_span), span),
+            (_, _, Some(keyword)) => {
+                SpannedCondition::required(self.term(keyword, child_span), span)
+            }
+        }
+    }
+
+    // `title:/pattern/` is targeted in a word, the invalid pattern is dropped and the too large max edits is reduced,
+    // both with the errors
+    fn term(&mut self, term: &str, span: Span) -> SpannedCondition {
+        if is_invalid_regex(term) {
+            self.errors.push(SyntaxError::InvalidRegex(span))
+        }
+        let condition = term_condition(term.into(), self.exists_alias.as_deref());
+        if is_too_many_edits(term, &condition) {
+            self.errors.push(SyntaxError::TooManyEdits(span))
+        }
+        SpannedCondition::term(condition, span)
+    }
+
+    fn empty_span(&self) -> Span {
+        Span {
+            start: self.position,
+            end: self.position,
+        }
+    }
+}
+
+// an and_expr without any unary
+fn is_empty(condition: &SpannedCondition) -> bool {
+    condition.children.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bnf_approach::token::Tokenizer;
+    use crate::{Boost, Condition, ConditionNode};
+
+    fn parse(query: &str) -> Condition {
+        parse_with_span(query).condition()
+    }
+
+    fn parse_with_span(query: &str) -> SpannedCondition {
+        ConditionParser::new(Tokenizer::new().tokenize(query).0)
+            .parse()
+            .0
+    }
+
+    fn errors(query: &str) -> Vec<SyntaxError> {
+        ConditionParser::new(Tokenizer::new().tokenize(query).0)
+            .parse()
+            .1
+    }
+
+    fn span_of<'a>(query: &'a str, condition: &SpannedCondition) -> &'a str {
+        &query[condition.span.start..condition.span.end]
+    }
+
+    mod test_parse {
+        use super::*;
+
+        #[test]
+        fn test_parse_empty() {
+            assert_eq!(parse(""), Condition::None)
+        }
+
+        #[test]
+        fn test_parse_only_operators() {
+            assert_eq!(parse("AND OR and or"), Condition::None)
+        }
+
+        #[test]
+        fn test_parse_and_has_higher_priority_than_or() {
+            assert_eq!(
+                parse("Ａ１ OR Ａ２ Ａ３ AND (Ａ４)"),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("Ａ１".into()),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("Ａ２".into()),
+                                Condition::required(Condition::Keyword("Ａ３".into())),
+                                Condition::required(Condition::Keyword("Ａ４".into())),
+                            ]
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_near_has_higher_priority_than_and() {
+            assert_eq!(
+                parse("Ａ１ OR Ａ２ Ａ３ NEAR/5 Ａ４ ONEAR/1 Ａ５"),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("Ａ１".into()),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("Ａ２".into()),
+                                Condition::Operator(
+                                    Operator::OrderedNear(1),
+                                    vec![
+                                        Condition::Operator(
+                                            Operator::Near(5),
+                                            vec![
+                                                Condition::Keyword("Ａ３".into()),
+                                                Condition::Keyword("Ａ４".into()),
+                                            ]
+                                        ),
+                                        Condition::Keyword("Ａ５".into()),
+                                    ]
+                                ),
+                            ]
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_operators_without_keywords() {
+            assert_eq!(
+                parse("OR Ａ１ AND OR OR Ａ２ AND"),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("Ａ１".into()),
+                        Condition::Keyword("Ａ２".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_negative() {
+            assert_eq!(
+                parse("-Ａ１ -\"Ｐ１\" -(Ａ２ OR Ａ３) -"),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::Keyword("Ａ１".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("Ｐ１".into()))),
+                        Condition::Not(Box::new(Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Keyword("Ａ２".into()),
+                                Condition::Keyword("Ａ３".into()),
+                            ]
+                        ))),
+                        Condition::Keyword("-".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_target() {
+            assert_eq!(
+                parse("title:Ａ１ title:\"Ｐ１\" -title:\"ＮＰ１\" title:(Ａ２ OR Ａ３)"),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Keyword("Ａ１".into()))
+                        ),
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::PhraseKeyword("Ｐ１".into()))
+                        ),
+                        Condition::Not(Box::new(Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::PhraseKeyword("ＮＰ１".into()))
+                        ))),
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Operator(
+                                Operator::Or,
+                                vec![
+                                    Condition::Keyword("Ａ２".into()),
+                                    Condition::Keyword("Ａ３".into()),
+                                ]
+                            ))
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_boost() {
+            let boosted = |condition: Condition, boost: f32| Condition::Boosted {
+                condition: Box::new(condition),
+                boost: Boost::new(boost).unwrap(),
+            };
+            assert_eq!(
+                parse("Ａ１^2 -\"Ｐ１\"^0.5 title:(Ａ２ OR Ａ３)^3 (Ａ４)^x"),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        boosted(Condition::Keyword("Ａ１".into()), 2.0),
+                        Condition::Not(Box::new(boosted(
+                            Condition::PhraseKeyword("Ｐ１".into()),
+                            0.5
+                        ))),
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(boosted(
+                                Condition::Operator(
+                                    Operator::Or,
+                                    vec![
+                                        Condition::Keyword("Ａ２".into()),
+                                        Condition::Keyword("Ａ３".into()),
+                                    ]
+                                ),
+                                3.0
+                            ))
+                        ),
+                        Condition::Keyword("Ａ４".into()),
+                        Condition::Keyword("^x".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_span_of_boost() {
+            let query = "-\"Ｐ１\"^2 title:(Ａ１ Ａ２)^0.5";
+            let actual = parse_with_span(query);
+            assert_eq!(span_of(query, &actual.children[0]), "-\"Ｐ１\"^2");
+            assert_eq!(
+                span_of(query, &actual.children[0].children[0]),
+                "\"Ｐ１\"^2"
+            );
+            assert_eq!(
+                span_of(query, &actual.children[1].children[0]),
+                "(Ａ１ Ａ２)^0.5"
+            );
+        }
+
+        #[test]
+        fn test_parse_unnecessary_nested_brackets() {
+            assert_eq!(
+                parse("((Ａ１ OR (Ａ２ OR Ａ３))) AND (Ａ４)"),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::required(Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Keyword("Ａ１".into()),
+                                Condition::Keyword("Ａ２".into()),
+                                Condition::Keyword("Ａ３".into()),
+                            ]
+                        )),
+                        Condition::required(Condition::Keyword("Ａ４".into())),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_parse_span_of_keywords() {
+            let query = "　-Ａ１ ＡＮＤ Ａ２ ";
+            let actual = parse_with_span(query);
+            assert_eq!(span_of(query, &actual), "-Ａ１ ＡＮＤ Ａ２");
+            assert_eq!(span_of(query, &actual.children[0]), "-Ａ１");
+            assert_eq!(span_of(query, &actual.children[0].children[0]), "Ａ１");
+            assert_eq!(span_of(query, &actual.children[1]), "Ａ２");
+        }
+
+        #[test]
+        fn test_parse_span_of_phrase_and_group() {
+            let query = "-title:\"Ｐ １\" OR （Ａ１ OR Ａ２）";
+            let actual = parse_with_span(query);
+            let negative = &actual.children[0];
+            assert_eq!(span_of(query, negative), "-title:\"Ｐ １\"");
+            assert_eq!(span_of(query, &negative.children[0]), "title:\"Ｐ １\"");
+            assert_eq!(
+                span_of(query, &negative.children[0].children[0]),
+                "\"Ｐ １\""
+            );
+            // the flattened group doesn't remain
+            assert_eq!(span_of(query, &actual.children[2]), "Ａ２");
+
+            let query = "Ａ１ -(Ａ２ Ａ３)";
+            let actual = parse_with_span(query);
+            assert_eq!(span_of(query, &actual.children[1]), "-(Ａ２ Ａ３)");
+            assert_eq!(
+                span_of(query, &actual.children[1].children[0]),
+                "(Ａ２ Ａ３)"
+            );
+        }
+
+        #[test]
+        fn test_parse_span_condition_is_consistent_with_children() {
+            fn assert_consistent(spanned: &SpannedCondition) {
+                match &spanned.node {
+                    ConditionNode::Leaf(_) => assert!(spanned.children.is_empty()),
+                    ConditionNode::Not | ConditionNode::Targeted(_) | ConditionNode::Boosted(_) => {
+                        assert_eq!(spanned.children.len(), 1)
+                    }
+                    ConditionNode::Boolean(occurs) => {
+                        assert_eq!(occurs.len(), spanned.children.len())
+                    }
+                    ConditionNode::Operator(_) => {}
+                }
+                spanned.children.iter().for_each(assert_consistent);
+            }
+            [
+                "",
+                "Ａ OR -(Ｂ title:\"Ｃ\") AND -(-(Ｄ))",
+                "((Ａ OR (Ｂ OR Ｃ))) AND (Ｄ) () \"\" OR",
+                "title:(Ａ Ｂ)^2 -\"Ｃ\"~1^0.5 title:Ｄ^3",
+            ]
+            .iter()
+            .for_each(|query| assert_consistent(&parse_with_span(query)));
+        }
+
+        #[test]
+        fn test_parse_dangling_operators() {
+            assert_eq!(
+                errors("OR Ａ AND OR OR Ｂ AND (and Ｃ or)"),
+                vec![
+                    SyntaxError::DanglingOperator(Span { start: 7, end: 10 }),
+                    SyntaxError::DanglingOperator(Span { start: 0, end: 2 }),
+                    SyntaxError::DanglingOperator(Span { start: 11, end: 13 }),
+                    SyntaxError::DanglingOperator(Span { start: 26, end: 29 }),
+                    SyntaxError::DanglingOperator(Span { start: 34, end: 36 }),
+                ]
+            );
+            assert_eq!(errors("Ａ AND Ｂ OR -Ｃ and (Ｄ or Ｅ)"), vec![]);
+            assert_eq!(
+                errors("NEAR/1 Ａ NEAR/2 OR Ｂ NEAR/3"),
+                vec![
+                    SyntaxError::DanglingOperator(Span { start: 0, end: 6 }),
+                    SyntaxError::DanglingOperator(Span { start: 11, end: 17 }),
+                    SyntaxError::DanglingOperator(Span { start: 25, end: 31 }),
+                ]
+            );
+            assert_eq!(errors("Ａ NEAR/1 (Ｂ OR Ｃ) ONEAR/2 -Ｄ"), vec![]);
+        }
+    }
+}
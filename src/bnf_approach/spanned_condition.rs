@@ -0,0 +1,408 @@
+use crate::{
+    Boost, Condition, ConditionNode, DefaultOperator, Occur, Operator, Span, SpannedCondition,
+};
+
+impl SpannedCondition {
+    // built from the nodes every time, it's the same as the condition parsed without the spans
+    pub fn condition(&self) -> Condition {
+        let mut children = self.children.iter().map(|child| child.condition());
+        let mut child = || Box::new(children.next().unwrap_or(Condition::None));
+        match &self.node {
+            ConditionNode::Leaf(condition) => condition.clone(),
+            ConditionNode::Not => Condition::Not(child()),
+            ConditionNode::Targeted(target) => Condition::Targeted(target.clone(), child()),
+            ConditionNode::Boosted(boost) => Condition::Boosted {
+                condition: child(),
+                boost: *boost,
+            },
+            ConditionNode::Boolean(occurs) => {
+                Condition::Boolean(occurs.iter().copied().zip(children).collect())
+            }
+            ConditionNode::Operator(operator) => {
+                Condition::Operator(operator.clone(), children.collect())
+            }
+        }
+    }
+
+    pub(crate) fn leaf(condition: Condition, span: Span) -> Self {
+        Self {
+            node: ConditionNode::Leaf(condition),
+            span,
+            children: vec![],
+        }
+    }
+
+    pub(crate) fn none(span: Span) -> Self {
+        Self::leaf(Condition::None, span)
+    }
+
+    pub(crate) fn is_none(&self) -> bool {
+        matches!(self.node, ConditionNode::Leaf(Condition::None))
+    }
+
+    fn node(node: ConditionNode, children: Vec<SpannedCondition>, span: Span) -> Self {
+        Self {
+            node,
+            span,
+            children,
+        }
+    }
+
+    pub(crate) fn not(child: SpannedCondition, span: Span) -> Self {
+        Self::node(ConditionNode::Not, vec![child], span)
+    }
+
+    pub(crate) fn required(child: SpannedCondition, span: Span) -> Self {
+        Self::node(ConditionNode::Boolean(vec![Occur::Must]), vec![child], span)
+    }
+
+    // the same as `Condition::required_by_and`, the span of `+` is not in the query, so it's the same as the one of the child
+    pub(crate) fn required_by_and(self) -> Self {
+        match &self.node {
+            ConditionNode::Not => self,
+            ConditionNode::Boolean(occurs) if occurs == &[Occur::Must] => self,
+            _ => {
+                let span = self.span;
+                Self::required(self, span)
+            }
+        }
+    }
+
+    pub(crate) fn group(child: SpannedCondition) -> Self {
+        let span = child.span;
+        Self::node(
+            ConditionNode::Boolean(vec![Occur::Should]),
+            vec![child],
+            span,
+        )
+    }
+
+    pub(crate) fn targeted(target: String, child: SpannedCondition, span: Span) -> Self {
+        Self::node(ConditionNode::Targeted(target), vec![child], span)
+    }
+
+    pub(crate) fn boosted(child: SpannedCondition, boost: Boost, span: Span) -> Self {
+        Self::node(ConditionNode::Boosted(boost), vec![child], span)
+    }
+
+    // the condition of a word like `title:rust^2`, the nodes in it share the span of the word
+    pub(crate) fn term(condition: Condition, span: Span) -> Self {
+        match condition {
+            Condition::Targeted(target, condition) => {
+                Self::targeted(target, Self::term(*condition, span), span)
+            }
+            Condition::Boosted { condition, boost } => {
+                Self::boosted(Self::term(*condition, span), boost, span)
+            }
+            condition => Self::leaf(condition, span),
+        }
+    }
+
+    pub(crate) fn operator(
+        operator: Operator, children: Vec<SpannedCondition>, span: Span,
+    ) -> Self {
+        Self::node(ConditionNode::Operator(operator), children, span)
+    }
+
+    fn ungroup(mut self) -> Self {
+        match (&self.node, self.children.len()) {
+            (ConditionNode::Boolean(occurs), 1) if occurs == &[Occur::Should] => {
+                self.children.remove(0)
+            }
+            _ => self,
+        }
+    }
+
+    // the same as `Condition::is_kept_group`
+    fn is_kept_group(&self) -> bool {
+        matches!(self.node, ConditionNode::Operator(Operator::And))
+            && self.children.iter().any(|child| match &child.node {
+                ConditionNode::Not => true,
+                ConditionNode::Boolean(occurs) => {
+                    occurs.iter().any(|occur| occur != &Occur::Should)
+                }
+                _ => false,
+            })
+    }
+
+    // the same as `Condition::simplify`, the removed nodes take their spans with them
+    pub(crate) fn simplify(self) -> Self {
+        let Self {
+            node,
+            span,
+            children,
+        } = self;
+        let child = |children: Vec<SpannedCondition>| {
+            children
+                .into_iter()
+                .next()
+                .map(|child| child.simplify().ungroup())
+                .unwrap_or_else(|| Self::none(span))
+        };
+        match node {
+            ConditionNode::Not => {
+                let child = child(children);
+                match child.node {
+                    _ if child.is_none() => Self::none(span),
+                    ConditionNode::Not => child
+                        .children
+                        .into_iter()
+                        .next()
+                        .unwrap_or_else(|| Self::none(span)),
+                    _ => Self::not(child, span),
+                }
+            }
+            ConditionNode::Targeted(target) => match child(children) {
+                child if child.is_none() => Self::none(span),
+                child => Self::targeted(target, child, span),
+            },
+            ConditionNode::Boosted(boost) => match child(children) {
+                child if child.is_none() => Self::none(span),
+                child => Self::boosted(child, boost, span),
+            },
+            ConditionNode::Operator(operator) => {
+                let mut children = children
+                    .into_iter()
+                    .map(|child| match operator {
+                        Operator::And => child.simplify(),
+                        _ => child.simplify().ungroup(),
+                    })
+                    .filter(|child| !child.is_none())
+                    .collect::<Vec<SpannedCondition>>();
+                match children.len() {
+                    0 => Self::none(span),
+                    // the brackets of a group stay in the span of the operator left in it
+                    1 => match children.remove(0) {
+                        child @ Self {
+                            node: ConditionNode::Operator(_),
+                            ..
+                        } => Self { span, ..child },
+                        child => child,
+                    },
+                    _ => Self::operator(
+                        operator.clone(),
+                        children
+                            .into_iter()
+                            .flat_map(|child| match &child.node {
+                                ConditionNode::Operator(child_operator)
+                                    if child_operator == &operator =>
+                                {
+                                    child.children
+                                }
+                                _ => vec![child],
+                            })
+                            .collect(),
+                        span,
+                    ),
+                }
+            }
+            ConditionNode::Boolean(occurs) => {
+                let (occurs, mut children): (Vec<Occur>, Vec<SpannedCondition>) = occurs
+                    .into_iter()
+                    .zip(children.into_iter().map(|child| child.simplify()))
+                    .filter(|(_, child)| !child.is_none())
+                    .unzip();
+                match (occurs.as_slice(), children.as_slice()) {
+                    ([], _) => Self::none(span),
+                    ([Occur::Should], [child]) if !child.is_kept_group() => children.remove(0),
+                    _ => Self::node(ConditionNode::Boolean(occurs), children, span),
+                }
+            }
+            ConditionNode::Leaf(condition) => Self::leaf(condition.simplify(), span),
+        }
+    }
+
+    // the same as `Condition::apply_default_operator`, the clauses keep the spans of their conditions
+    pub(crate) fn apply_default_operator(self, default_operator: DefaultOperator) -> Self {
+        let Self {
+            node,
+            span,
+            children,
+        } = self;
+        match node {
+            ConditionNode::Operator(Operator::And) if default_operator == DefaultOperator::Or => {
+                let (occurs, children) = children
+                    .into_iter()
+                    .map(|child| child.into_clause(default_operator))
+                    .unzip();
+                Self::node(ConditionNode::Boolean(occurs), children, span)
+            }
+            ConditionNode::Boolean(occurs) => into_operators(
+                occurs
+                    .into_iter()
+                    .zip(
+                        children
+                            .into_iter()
+                            .map(|child| child.apply_default_operator(default_operator)),
+                    )
+                    .collect(),
+                span,
+            ),
+            node => Self::node(
+                node,
+                children
+                    .into_iter()
+                    .map(|child| child.apply_default_operator(default_operator))
+                    .collect(),
+                span,
+            ),
+        }
+    }
+
+    fn into_clause(mut self, default_operator: DefaultOperator) -> (Occur, Self) {
+        match (&self.node, self.children.len()) {
+            (ConditionNode::Not, 1) => (
+                Occur::MustNot,
+                self.children
+                    .remove(0)
+                    .apply_default_operator(default_operator),
+            ),
+            (ConditionNode::Boolean(occurs), 1) => (
+                occurs[0],
+                self.children
+                    .remove(0)
+                    .apply_default_operator(default_operator),
+            ),
+            _ => (Occur::Should, self.apply_default_operator(default_operator)),
+        }
+    }
+}
+
+// the same as the one of `Condition`, the should clauses are joined by `OR` in the span of them
+fn into_operators(clauses: Vec<(Occur, SpannedCondition)>, span: Span) -> SpannedCondition {
+    let has_must = clauses.iter().any(|(occur, _)| occur == &Occur::Must);
+    let (shoulds, others): (Vec<_>, Vec<_>) = clauses
+        .into_iter()
+        .partition(|(occur, _)| occur == &Occur::Should);
+    let mut children = others
+        .into_iter()
+        .map(|(occur, child)| match occur {
+            Occur::MustNot => {
+                let span = child.span;
+                SpannedCondition::not(child, span)
+            }
+            _ => child,
+        })
+        .collect::<Vec<SpannedCondition>>();
+    if !has_must {
+        let shoulds = shoulds
+            .into_iter()
+            .map(|(_, child)| child)
+            .collect::<Vec<SpannedCondition>>();
+        let span = cover(shoulds.iter().map(|child| child.span)).unwrap_or(span);
+        children.push(SpannedCondition::operator(Operator::Or, shoulds, span))
+    }
+    SpannedCondition::operator(Operator::And, children, span)
+}
+
+// the span from the start of the first one to the end of the last one
+pub(crate) fn cover(spans: impl IntoIterator<Item = Span>) -> Option<Span> {
+    spans.into_iter().fold(None, |covered, span| match covered {
+        None => Some(span),
+        Some(covered) => Some(Span {
+            start: covered.start.min(span.start),
+            end: covered.end.max(span.end),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyword(keyword: &str, start: usize, end: usize) -> SpannedCondition {
+        SpannedCondition::leaf(Condition::Keyword(keyword.into()), Span { start, end })
+    }
+
+    mod test_condition {
+        use super::*;
+
+        #[test]
+        fn test_condition_from_nodes() {
+            let actual = SpannedCondition::boosted(
+                SpannedCondition::operator(
+                    Operator::And,
+                    vec![
+                        keyword("a", 1, 2),
+                        SpannedCondition::not(keyword("b", 4, 5), Span { start: 3, end: 5 }),
+                    ],
+                    Span { start: 1, end: 5 },
+                ),
+                Boost::new(2.0).unwrap(),
+                Span { start: 0, end: 8 },
+            );
+            assert_eq!(
+                actual.condition(),
+                Condition::Boosted {
+                    condition: Box::new(Condition::Operator(
+                        Operator::And,
+                        vec![
+                            Condition::Keyword("a".into()),
+                            Condition::Not(Box::new(Condition::Keyword("b".into())))
+                        ]
+                    )),
+                    boost: Boost::new(2.0).unwrap(),
+                }
+            );
+            assert_eq!(
+                actual.node,
+                ConditionNode::Boosted(Boost::new(2.0).unwrap())
+            )
+        }
+    }
+
+    mod test_simplify {
+        use super::*;
+
+        #[test]
+        fn test_simplify_double_not() {
+            let actual = SpannedCondition::not(
+                SpannedCondition::not(keyword("Ａ", 2, 5), Span { start: 1, end: 5 }),
+                Span { start: 0, end: 5 },
+            )
+            .simplify();
+            assert_eq!(actual, keyword("Ａ", 2, 5))
+        }
+
+        #[test]
+        fn test_simplify_operator() {
+            let actual = SpannedCondition::operator(
+                Operator::And,
+                vec![
+                    SpannedCondition::operator(
+                        Operator::And,
+                        vec![keyword("a", 1, 2), keyword("b", 3, 4)],
+                        Span { start: 0, end: 5 },
+                    ),
+                    SpannedCondition::operator(Operator::Or, vec![], Span { start: 6, end: 6 }),
+                    SpannedCondition::operator(
+                        Operator::Or,
+                        vec![keyword("c", 7, 8)],
+                        Span { start: 7, end: 8 },
+                    ),
+                ],
+                Span { start: 0, end: 8 },
+            )
+            .simplify();
+            assert_eq!(
+                actual,
+                SpannedCondition::operator(
+                    Operator::And,
+                    vec![keyword("a", 1, 2), keyword("b", 3, 4), keyword("c", 7, 8)],
+                    Span { start: 0, end: 8 },
+                )
+            )
+        }
+
+        #[test]
+        fn test_simplify_targeted_none() {
+            let actual = SpannedCondition::targeted(
+                "title".into(),
+                SpannedCondition::none(Span { start: 6, end: 6 }),
+                Span { start: 0, end: 6 },
+            )
+            .simplify();
+            assert_eq!(actual, SpannedCondition::none(Span { start: 0, end: 6 }))
+        }
+    }
+}
@@ -0,0 +1,763 @@
+use crate::escape::EscapedQuery;
+use crate::near::near_operator;
+use crate::term::RANGE_PATTERN;
+use crate::{Operator, Span, SyntaxError};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static REGEX_AND: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(?i)[AＡ][NＮ][DＤ]$").unwrap());
+static REGEX_OR: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(?i)[OＯ][RＲ]$").unwrap());
+static REGEX_SYMBOLIC_AND: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("^(?:(?i)[AＡ][NＮ][DＤ]|[&＆]{1,2})$").unwrap());
+static REGEX_SYMBOLIC_OR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("^(?:(?i)[OＯ][RＲ]|[|｜]{1,2})$").unwrap());
+static REGEX_NOT: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(?i)[NＮ][OＯ][TＴ]$").unwrap());
+static REGEX_RANGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("^{}", RANGE_PATTERN)).unwrap());
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum Token {
+    Word(String),
+    // with the slop of `"phrase"~5`
+    Phrase(String, Option<String>),
+    // `-` or `!` just before a phrase or brackets, and `NOT`
+    Minus,
+    // `+` just before a phrase or brackets
+    Plus,
+    // `target:` just before a phrase or brackets
+    Target(String),
+    Open,
+    Close,
+    And,
+    Or,
+    // `NEAR/5` and `ONEAR/5`
+    Near(Operator),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Symbol {
+    Char(usize, char),
+    Space,
+    Phrase(String, Option<String>, Span),
+    Open(Span),
+    Close(Span),
+}
+
+// the characters of a word with their byte offsets,
+// they are not always continuous in the query because the ignored symbols are skipped
+#[derive(Debug, Default)]
+struct Word(Vec<(usize, char)>);
+
+impl Word {
+    fn text(&self) -> String {
+        self.0.iter().map(|(_, c)| c).collect()
+    }
+
+    fn span(&self) -> Option<Span> {
+        match (self.0.first(), self.0.last()) {
+            (Some((start, _)), Some((last, c))) => Some(Span {
+                start: *start,
+                end: last + c.len_utf8(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn split_off(&mut self, at: usize) -> Word {
+        Word(self.0.split_off(at))
+    }
+
+    // `!` at the beginning is the same as `-`, but `!` alone is a keyword like `-`
+    fn normalize_negation(&mut self) {
+        if let [(i, '!' | '！'), _, ..] = self.0[..] {
+            self.0[0] = (i, '-')
+        }
+    }
+}
+
+pub(crate) struct Tokenizer {
+    and_regex: Regex,
+    or_regex: Regex,
+    not_regex: Regex,
+}
+
+impl Tokenizer {
+    pub(crate) fn new() -> Self {
+        Self {
+            and_regex: REGEX_AND.clone(),
+            or_regex: REGEX_OR.clone(),
+            not_regex: REGEX_NOT.clone(),
+        }
+    }
+
+    // `&&`, `&`, `||` and `|` are the same as `AND` and `OR`
+    pub(crate) fn with_symbolic_operators(mut self, symbolic_operators: bool) -> Self {
+        (self.and_regex, self.or_regex) = match symbolic_operators {
+            true => (REGEX_SYMBOLIC_AND.clone(), REGEX_SYMBOLIC_OR.clone()),
+            false => (REGEX_AND.clone(), REGEX_OR.clone()),
+        };
+        self
+    }
+
+    // the incorrect parts of the query are corrected and reported as errors,
+    // the escaped characters are a part of the words as they are typed, and the spans are the ones in the original query
+    pub(crate) fn tokenize(&self, query: &str) -> (Vec<(Token, Span)>, Vec<SyntaxError>) {
+        let escaped = EscapedQuery::new(query);
+        let (tokens, errors) = self.tokenize_escaped(&escaped.query);
+        let restore = |text: String| escaped.escapes.restore(&text);
+        (
+            tokens
+                .into_iter()
+                .map(|(token, span)| {
+                    let token = match token {
+                        Token::Word(word) => Token::Word(restore(word)),
+                        Token::Phrase(phrase, slop) => Token::Phrase(restore(phrase), slop),
+                        Token::Target(target) => Token::Target(restore(target)),
+                        token => token,
+                    };
+                    (token, escaped.original_span(span))
+                })
+                .collect(),
+            errors
+                .into_iter()
+                .map(|error| {
+                    let span = escaped.original_span(error.span());
+                    error.with_span(span)
+                })
+                .collect(),
+        )
+    }
+
+    fn tokenize_escaped(&self, query: &str) -> (Vec<(Token, Span)>, Vec<SyntaxError>) {
+        let mut errors = Vec::<SyntaxError>::new();
+        let mut tokens = Vec::<(Token, Span)>::new();
+        let mut word = Word::default();
+        let symbols = attach_slops(Self::symbolize(query, &mut errors));
+        for symbol in Self::remove_empty_brackets(symbols, &mut errors) {
+            match symbol {
+                Symbol::Char(i, c) => word.0.push((i, c)),
+                Symbol::Space => self.flush_word(word.split_off(0), &mut tokens),
+                Symbol::Phrase(phrase, slop, span) => {
+                    let prefix = take_phrase_prefix(&mut word);
+                    if is_not_blank(&phrase) {
+                        self.flush_word(word.split_off(0), &mut tokens);
+                        tokens.extend(prefix);
+                        tokens.push((Token::Phrase(phrase, slop), span))
+                    } else {
+                        errors.push(SyntaxError::EmptyPhrase(span))
+                    }
+                }
+                Symbol::Open(span) => {
+                    let prefix = take_bracket_prefix(&mut word);
+                    self.flush_word(word.split_off(0), &mut tokens);
+                    tokens.extend(prefix);
+                    tokens.push((Token::Open, span))
+                }
+                Symbol::Close(span) => {
+                    self.flush_word(word.split_off(0), &mut tokens);
+                    tokens.push((Token::Close, span))
+                }
+            }
+        }
+        self.flush_word(word, &mut tokens);
+        (tokens, errors)
+    }
+
+    // double quotations are paired from left to right, and brackets out of phrases are paired with the nearest one,
+    // the double quotations and brackets without pair are ignored
+    fn symbolize(query: &str, errors: &mut Vec<SyntaxError>) -> Vec<Symbol> {
+        let last_double_quotation = match query.matches(['"', '”']).count() % 2 {
+            0 => None,
+            _ => query.rfind(['"', '”']),
+        };
+        let mut symbols = Vec::<Option<Symbol>>::new();
+        let mut open_brackets = Vec::<(usize, Span)>::new();
+        let mut phrase = Option::<(usize, String)>::None;
+        let mut range_end = 0;
+        for (i, c) in query.char_indices() {
+            let span = Span {
+                start: i,
+                end: i + c.len_utf8(),
+            };
+            if phrase.is_none() && i >= range_end {
+                range_end = i + range_len(query, i).unwrap_or_default();
+            }
+            match (&mut phrase, c) {
+                // the spaces and brackets of `target:[1 TO 2]` are a part of the word
+                (None, c) if i < range_end => symbols.push(Some(Symbol::Char(i, c))),
+                (Some((start, p)), '"' | '”') => {
+                    symbols.push(Some(Symbol::Phrase(
+                        p.clone(),
+                        None,
+                        Span {
+                            start: *start,
+                            end: span.end,
+                        },
+                    )));
+                    phrase = None
+                }
+                (Some((_, p)), c) => p.push(c),
+                (None, '"' | '”') if Some(i) != last_double_quotation => {
+                    phrase = Some((i, String::new()))
+                }
+                (None, '"' | '”') => errors.push(SyntaxError::UnterminatedQuote(span)),
+                (None, ' ' | '　') => symbols.push(Some(Symbol::Space)),
+                (None, '(' | '（') => {
+                    open_brackets.push((symbols.len(), span));
+                    symbols.push(None)
+                }
+                (None, ')' | '）') => match open_brackets.pop() {
+                    Some((open, open_span)) => {
+                        symbols[open] = Some(Symbol::Open(open_span));
+                        symbols.push(Some(Symbol::Close(span)))
+                    }
+                    None => errors.push(SyntaxError::UnbalancedBracket(span)),
+                },
+                (None, c) => symbols.push(Some(Symbol::Char(i, c))),
+            }
+        }
+        errors.extend(
+            open_brackets
+                .into_iter()
+                .map(|(_, span)| SyntaxError::UnbalancedBracket(span)),
+        );
+        symbols.into_iter().flatten().collect()
+    }
+
+    // brackets with only spaces or blank phrases in them are removed, like they have never been typed
+    fn remove_empty_brackets(symbols: Vec<Symbol>, errors: &mut Vec<SyntaxError>) -> Vec<Symbol> {
+        let mut removed = Vec::<Symbol>::new();
+        let mut open_brackets = Vec::<usize>::new();
+        for symbol in symbols {
+            match symbol {
+                Symbol::Open(_) => {
+                    open_brackets.push(removed.len());
+                    removed.push(symbol)
+                }
+                Symbol::Close(close) => {
+                    let open = open_brackets.pop().unwrap_or_default();
+                    let is_empty = removed[open + 1..].iter().all(|symbol| match symbol {
+                        Symbol::Space => true,
+                        Symbol::Phrase(phrase, ..) => !is_not_blank(phrase),
+                        _ => false,
+                    });
+                    match (is_empty, &removed[open]) {
+                        (true, Symbol::Open(open_span)) => {
+                            let span = Span {
+                                start: open_span.start,
+                                end: close.end,
+                            };
+                            // only the outermost one of the nested empty brackets is reported
+                            errors.retain(|error| {
+                                !matches!(error, SyntaxError::EmptyGroup(inner) if inner.start > span.start)
+                            });
+                            errors.push(SyntaxError::EmptyGroup(span));
+                            removed.truncate(open)
+                        }
+                        _ => removed.push(symbol),
+                    }
+                }
+                _ => removed.push(symbol),
+            }
+        }
+        removed
+    }
+
+    fn flush_word(&self, mut word: Word, tokens: &mut Vec<(Token, Span)>) {
+        word.normalize_negation();
+        if let Some(span) = word.span() {
+            let text = word.text();
+            tokens.push((
+                if self.and_regex.is_match(&text) {
+                    Token::And
+                } else if self.or_regex.is_match(&text) {
+                    Token::Or
+                } else if self.not_regex.is_match(&text) {
+                    Token::Minus
+                } else if let Some(operator) = near_operator(&text) {
+                    Token::Near(operator)
+                } else {
+                    Token::Word(text)
+                },
+                span,
+            ))
+        }
+    }
+}
+
+// the length of the range which starts at `i` just after `target:`
+fn range_len(query: &str, i: usize) -> Option<usize> {
+    let is_delimiter = |c: char| c.is_whitespace() || "\"”()（）:：[]{}".contains(c);
+    let target = query[..i].strip_suffix([':', '：'])?;
+    target
+        .rsplit(is_delimiter)
+        .next()
+        .filter(|target| target.chars().any(|c| c != '-'))?;
+    REGEX_RANGE.find(&query[i..]).map(|range| range.end())
+}
+
+// `~` and the digits just after a phrase are its slop, like `"phrase"~5`
+fn attach_slops(symbols: Vec<Symbol>) -> Vec<Symbol> {
+    let mut attached = Vec::<Symbol>::new();
+    let mut symbols = symbols.into_iter().peekable();
+    while let Some(symbol) = symbols.next() {
+        attached.push(match symbol {
+            Symbol::Phrase(phrase, _, span) => match symbols
+                .next_if(|symbol| matches!(symbol, Symbol::Char(i, '~' | '～') if *i == span.end))
+            {
+                Some(Symbol::Char(i, tilde)) => {
+                    let mut slop = String::new();
+                    let mut end = i + tilde.len_utf8();
+                    while let Some(Symbol::Char(_, digit)) = symbols.next_if(|symbol| {
+                        matches!(symbol, Symbol::Char(i, c) if *i == end && c.is_ascii_digit())
+                    }) {
+                        slop.push(digit);
+                        end += 1
+                    }
+                    Symbol::Phrase(
+                        phrase,
+                        Some(slop),
+                        Span {
+                            start: span.start,
+                            end,
+                        },
+                    )
+                }
+                _ => Symbol::Phrase(phrase, None, span),
+            },
+            symbol => symbol,
+        })
+    }
+    attached
+}
+
+// `-"phrase"`, `target:"phrase"` and `-target:"phrase"`
+fn take_phrase_prefix(word: &mut Word) -> Vec<(Token, Span)> {
+    word.normalize_negation();
+    let text = word.text();
+    if text.ends_with(['-', '!', '！', '+']) {
+        return sign(word.split_off(word.0.len() - 1));
+    }
+    match split_target(&text) {
+        Some(target) => match target.strip_prefix(['-', '+']) {
+            Some(target) if is_valid_phrase_target(target) => {
+                let mut prefix = word.split_off(0);
+                let target_word = prefix.split_off(1);
+                [
+                    sign(prefix),
+                    vec![(Token::Target(target.into()), span_of(&target_word))],
+                ]
+                .concat()
+            }
+            None if is_valid_phrase_target(&target) => {
+                let target_word = word.split_off(0);
+                vec![(Token::Target(target), span_of(&target_word))]
+            }
+            _ => vec![],
+        },
+        None => vec![],
+    }
+}
+
+// `-(brackets)`, `+(brackets)`, `target:(brackets)`, `-target:(brackets)` and `+target:(brackets)`
+fn take_bracket_prefix(word: &mut Word) -> Vec<(Token, Span)> {
+    word.normalize_negation();
+    let text = word.text();
+    match split_target(&text) {
+        Some(target) if !target.contains([':', '：']) => match target.strip_prefix(['-', '+']) {
+            Some(target) if is_valid_phrase_target(target) => {
+                let mut prefix = word.split_off(0);
+                let target_word = prefix.split_off(1);
+                [
+                    sign(prefix),
+                    vec![(Token::Target(target.into()), span_of(&target_word))],
+                ]
+                .concat()
+            }
+            Some(_) => vec![],
+            None => {
+                let target_word = word.split_off(0);
+                vec![(Token::Target(target), span_of(&target_word))]
+            }
+        },
+        _ => match text.ends_with(['-', '!', '！', '+']) {
+            true => sign(word.split_off(word.0.len() - 1)),
+            false => vec![],
+        },
+    }
+}
+
+// `-` or `+` before a phrase or brackets, `!` is already normalized to `-`
+fn sign(word: Word) -> Vec<(Token, Span)> {
+    let token = match word.text().as_str() {
+        "+" => Token::Plus,
+        _ => Token::Minus,
+    };
+    vec![(token, span_of(&word))]
+}
+
+fn span_of(word: &Word) -> Span {
+    word.span().unwrap_or_default()
+}
+
+fn split_target(text: &str) -> Option<String> {
+    text.strip_suffix([':', '：'])
+        .filter(|target| !target.is_empty())
+        .map(|target| target.to_string())
+}
+
+fn is_valid_phrase_target(target: &str) -> bool {
+    !target.is_empty() && !target.starts_with(['-', ':', '：'])
+}
+
+fn is_not_blank(value: &str) -> bool {
+    !value.replace([' ', '　'], "").is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::escape::unescape;
+
+    fn word(word: &str) -> Token {
+        Token::Word(word.into())
+    }
+
+    fn phrase(phrase: &str) -> Token {
+        Token::Phrase(phrase.into(), None)
+    }
+
+    fn target(target: &str) -> Token {
+        Token::Target(target.into())
+    }
+
+    fn tokens(query: &str) -> Vec<Token> {
+        Tokenizer::new()
+            .tokenize(query)
+            .0
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    mod test_tokenize {
+        use super::*;
+
+        #[test]
+        fn test_tokenize_empty() {
+            assert_eq!(tokens(" 　 "), vec![])
+        }
+
+        #[test]
+        fn test_tokenize_keywords_and_operators() {
+            assert_eq!(
+                tokens("Ａ１ and Ａ２　ＯＲ -Ａ３ - Ａ４"),
+                vec![
+                    word("Ａ１"),
+                    Token::And,
+                    word("Ａ２"),
+                    Token::Or,
+                    word("-Ａ３"),
+                    word("-"),
+                    word("Ａ４"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_phrase_keywords() {
+            assert_eq!(
+                tokens("Ａ１\"Ｐ１ (or) Ｐ２\"Ａ２ -”ＮＰ１” title:\"Ｐ３\" -title:\"ＮＰ２\""),
+                vec![
+                    word("Ａ１"),
+                    phrase("Ｐ１ (or) Ｐ２"),
+                    word("Ａ２"),
+                    Token::Minus,
+                    phrase("ＮＰ１"),
+                    target("title"),
+                    phrase("Ｐ３"),
+                    Token::Minus,
+                    target("title"),
+                    phrase("ＮＰ２"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_brackets() {
+            assert_eq!(
+                tokens("Ａ１(Ａ２ -（Ａ３）)Ａ４ title:(Ａ５)"),
+                vec![
+                    word("Ａ１"),
+                    Token::Open,
+                    word("Ａ２"),
+                    Token::Minus,
+                    Token::Open,
+                    word("Ａ３"),
+                    Token::Close,
+                    Token::Close,
+                    word("Ａ４"),
+                    target("title"),
+                    Token::Open,
+                    word("Ａ５"),
+                    Token::Close,
+                ]
+            );
+            assert_eq!(
+                tokens("-title:(Ａ６)"),
+                vec![
+                    Token::Minus,
+                    target("title"),
+                    Token::Open,
+                    word("Ａ６"),
+                    Token::Close,
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_brackets_without_pair() {
+            assert_eq!(
+                tokens("Ａ１) (Ａ２ (Ａ３) Ａ４("),
+                vec![
+                    word("Ａ１"),
+                    word("Ａ２"),
+                    Token::Open,
+                    word("Ａ３"),
+                    Token::Close,
+                    word("Ａ４"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_double_quotation_without_pair() {
+            assert_eq!(
+                tokens("\"Ｐ１\" \"Ａ１ Ａ２"),
+                vec![phrase("Ｐ１"), word("Ａ１"), word("Ａ２")]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_empty_brackets_and_phrase_keywords() {
+            assert_eq!(
+                tokens("Ａ１ ( \"\" (　)) Ａ２ \" \" -\"\" title:\"\" Ａ３"),
+                vec![word("Ａ１"), word("Ａ２"), word("Ａ３")]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_spans_in_bytes() {
+            // full width characters are 3 bytes in utf-8
+            assert_eq!(
+                Tokenizer::new().tokenize("Ａ１　-t:\"Ｐ\" (b)").0,
+                vec![
+                    (word("Ａ１"), Span { start: 0, end: 6 }),
+                    (Token::Minus, Span { start: 9, end: 10 }),
+                    (target("t"), Span { start: 10, end: 12 }),
+                    (phrase("Ｐ"), Span { start: 12, end: 17 }),
+                    (Token::Open, Span { start: 18, end: 19 }),
+                    (word("b"), Span { start: 19, end: 20 }),
+                    (Token::Close, Span { start: 20, end: 21 }),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_phrase_slops() {
+            assert_eq!(
+                Tokenizer::new()
+                    .tokenize("\"Ｐ１\"~5 \"Ｐ２\"～ \"Ｐ３\"~1Ａ \"Ｐ４\" ~2")
+                    .0,
+                vec![
+                    (
+                        Token::Phrase("Ｐ１".into(), Some("5".into())),
+                        Span { start: 0, end: 10 }
+                    ),
+                    (
+                        Token::Phrase("Ｐ２".into(), Some("".into())),
+                        Span { start: 11, end: 22 }
+                    ),
+                    (
+                        Token::Phrase("Ｐ３".into(), Some("1".into())),
+                        Span { start: 23, end: 33 }
+                    ),
+                    (word("Ａ"), Span { start: 33, end: 36 }),
+                    (phrase("Ｐ４"), Span { start: 37, end: 45 }),
+                    (word("~2"), Span { start: 46, end: 48 }),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_ranges() {
+            assert_eq!(
+                tokens("price:[1 TO 2] (-date:{* TO 3]) [4 TO 5]"),
+                vec![
+                    word("price:[1 TO 2]"),
+                    Token::Open,
+                    word("-date:{* TO 3]"),
+                    Token::Close,
+                    word("[4"),
+                    word("TO"),
+                    word("5]"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_near() {
+            assert_eq!(
+                tokens("Ａ NEAR/5 Ｂ ｏｎｅａｒ／２ Ｃ NEAR \"NEAR/1\""),
+                vec![
+                    word("Ａ"),
+                    Token::Near(Operator::Near(5)),
+                    word("Ｂ"),
+                    Token::Near(Operator::OrderedNear(2)),
+                    word("Ｃ"),
+                    word("NEAR"),
+                    phrase("NEAR/1"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_symbolic_operators() {
+            let tokenize = |symbolic_operators: bool| {
+                Tokenizer::new()
+                    .with_symbolic_operators(symbolic_operators)
+                    .tokenize("a && b ||(c) ＆ d | AT&T |||")
+                    .0
+                    .into_iter()
+                    .map(|(token, _)| token)
+                    .collect::<Vec<Token>>()
+            };
+            assert_eq!(
+                tokenize(true),
+                vec![
+                    word("a"),
+                    Token::And,
+                    word("b"),
+                    Token::Or,
+                    Token::Open,
+                    word("c"),
+                    Token::Close,
+                    Token::And,
+                    word("d"),
+                    Token::Or,
+                    word("AT&T"),
+                    word("|||"),
+                ]
+            );
+            assert_eq!(
+                tokenize(false),
+                vec![
+                    word("a"),
+                    word("&&"),
+                    word("b"),
+                    word("||"),
+                    Token::Open,
+                    word("c"),
+                    Token::Close,
+                    word("＆"),
+                    word("d"),
+                    word("|"),
+                    word("AT&T"),
+                    word("|||"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_negations() {
+            assert_eq!(
+                tokens("NOT a ｎｏｔ(b) !c !\"d\" !title:(e) ! NOTE"),
+                vec![
+                    Token::Minus,
+                    word("a"),
+                    Token::Minus,
+                    Token::Open,
+                    word("b"),
+                    Token::Close,
+                    word("-c"),
+                    Token::Minus,
+                    phrase("d"),
+                    Token::Minus,
+                    Token::Target("title".into()),
+                    Token::Open,
+                    word("e"),
+                    Token::Close,
+                    word("!"),
+                    word("NOTE"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_required() {
+            assert_eq!(
+                tokens("+a +\"b\" +(c) +title:(d) + a+b"),
+                vec![
+                    word("+a"),
+                    Token::Plus,
+                    phrase("b"),
+                    Token::Plus,
+                    Token::Open,
+                    word("c"),
+                    Token::Close,
+                    Token::Plus,
+                    Token::Target("title".into()),
+                    Token::Open,
+                    word("d"),
+                    Token::Close,
+                    word("+"),
+                    word("a+b"),
+                ]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_escaped_characters() {
+            let (tokens, errors) = Tokenizer::new().tokenize("\\(a \"b\\\"\" c\\)");
+            assert_eq!(errors, vec![]);
+            assert_eq!(
+                tokens
+                    .into_iter()
+                    .map(|(token, span)| match token {
+                        Token::Word(word) => (unescape(&word, &[]), span),
+                        Token::Phrase(phrase, _) => (unescape(&phrase, &[]), span),
+                        token => (format!("{:?}", token), span),
+                    })
+                    .collect::<Vec<(String, Span)>>(),
+                vec![
+                    ("(a".into(), Span { start: 0, end: 3 }),
+                    ("b\"".into(), Span { start: 4, end: 9 }),
+                    ("c)".into(), Span { start: 10, end: 13 }),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_tokenize_spans_of_word_with_ignored_bracket() {
+            assert_eq!(
+                Tokenizer::new().tokenize("Ａ)Ｂ").0,
+                vec![(word("ＡＢ"), Span { start: 0, end: 7 })]
+            )
+        }
+
+        #[test]
+        fn test_tokenize_errors() {
+            assert_eq!(
+                Tokenizer::new().tokenize("Ａ) \"\" (( )) (\"Ｐ\" \"Ｂ (").1,
+                vec![
+                    SyntaxError::UnbalancedBracket(Span { start: 3, end: 4 }),
+                    SyntaxError::UnterminatedQuote(Span { start: 21, end: 22 }),
+                    SyntaxError::UnbalancedBracket(Span { start: 14, end: 15 }),
+                    SyntaxError::UnbalancedBracket(Span { start: 26, end: 27 }),
+                    SyntaxError::EmptyGroup(Span { start: 8, end: 13 }),
+                    SyntaxError::EmptyPhrase(Span { start: 5, end: 7 }),
+                ]
+            )
+        }
+    }
+}
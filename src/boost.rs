@@ -0,0 +1,141 @@
+use crate::term::unescaped_indices;
+use crate::{Boost, Condition};
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+use std::sync::LazyLock;
+
+// `^2` and `^0.5`, the other ones like `^NaN` and `^2a` are a part of the keyword
+static REGEX_BOOST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\^(?P<boost>[0-9]+(?:\.[0-9]+)?)$").unwrap());
+
+// `f32` parses `NaN` and `inf`, and too many digits overflow to infinity, they are not weights
+pub(crate) fn parse_weight(weight: &str) -> Option<f32> {
+    weight
+        .parse::<f32>()
+        .ok()
+        .filter(|weight| weight.is_finite())
+}
+
+// the boost of `^2` written just after a term, a phrase or brackets
+pub(crate) fn boost(text: &str) -> Option<Boost> {
+    let captures = REGEX_BOOST.captures(text)?;
+    parse_weight(&captures["boost"]).and_then(Boost::new)
+}
+
+// `rust^2` is split into `rust` and `2`, the escaped `\^` is not a boost
+pub(crate) fn split_boost(term: &str) -> Option<(&str, Boost)> {
+    let (index, _) = unescaped_indices(term, &['^'])
+        .pop()
+        .filter(|(index, _)| *index > 0)?;
+    boost(&term[index..]).map(|boost| (&term[..index], boost))
+}
+
+impl Condition {
+    // the boost is on the term or the group itself, so it's inside the target like `title:(rust^2)`
+    pub(crate) fn boosted(self, boost: Boost) -> Self {
+        match self {
+            Condition::None => Condition::None,
+            Condition::Targeted(target, condition) => {
+                Condition::Targeted(target, Box::new(condition.boosted(boost)))
+            }
+            condition => Condition::Boosted {
+                condition: Box::new(condition),
+                boost,
+            },
+        }
+    }
+}
+
+impl Boost {
+    pub fn new(boost: f32) -> Option<Self> {
+        boost.is_finite().then_some(Self(boost))
+    }
+
+    pub fn value(&self) -> f32 {
+        self.0
+    }
+}
+
+// it's never NaN
+impl Eq for Boost {}
+
+impl TryFrom<f32> for Boost {
+    type Error = String;
+
+    fn try_from(boost: f32) -> Result<Self, Self::Error> {
+        Self::new(boost).ok_or(format!("the boost {} is not a finite number", boost))
+    }
+}
+
+impl From<Boost> for f32 {
+    fn from(boost: Boost) -> Self {
+        boost.0
+    }
+}
+
+// `2` and `0.5` as they are written in the query
+impl Display for Boost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod test_boost {
+        use super::*;
+
+        #[test]
+        fn test_split_boost() {
+            vec![
+                ("rust^2", Some(("rust", Boost::new(2.0).unwrap()))),
+                (
+                    "title:rust^0.5",
+                    Some(("title:rust", Boost::new(0.5).unwrap())),
+                ),
+                (
+                    "\"a b\"~2^10",
+                    Some(("\"a b\"~2", Boost::new(10.0).unwrap())),
+                ),
+                ("rust^NaN", None),
+                ("rust^inf", None),
+                ("rust^2a", None),
+                ("rust^-1", None),
+                ("title^2:rust", None),
+                ("^2", None),
+                (&*format!("rust^{}", "9".repeat(40)), None),
+            ]
+            .into_iter()
+            .for_each(|(term, expected)| assert_eq!(split_boost(term), expected, "{}", term))
+        }
+
+        #[test]
+        fn test_parse_weight() {
+            assert_eq!(parse_weight("0.2"), Some(0.2));
+            assert_eq!(parse_weight("-1"), Some(-1.0));
+            assert_eq!(parse_weight("NaN"), None);
+            assert_eq!(parse_weight("inf"), None);
+        }
+
+        #[test]
+        fn test_boosted() {
+            assert_eq!(
+                Condition::Targeted("title".into(), Box::new(Condition::Regex("a".into())))
+                    .boosted(Boost::new(2.0).unwrap()),
+                Condition::Targeted(
+                    "title".into(),
+                    Box::new(Condition::Boosted {
+                        condition: Box::new(Condition::Regex("a".into())),
+                        boost: Boost::new(2.0).unwrap()
+                    })
+                )
+            );
+            assert_eq!(
+                Condition::None.boosted(Boost::new(2.0).unwrap()),
+                Condition::None
+            )
+        }
+    }
+}
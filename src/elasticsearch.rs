@@ -0,0 +1,1087 @@
+use crate::{
+    Comparison, Condition, ConditionOnTarget, Occur, Operator, Schema, Target, TypedValue,
+    ValueType,
+};
+use serde_json::{json, Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryDsl {
+    default_fields: Vec<String>,
+    schema: Schema,
+}
+
+impl QueryDsl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the fields searched by the keywords without target, `field^boost` is also available
+    pub fn with_default_fields(mut self, default_fields: Vec<String>) -> Self {
+        self.default_fields = default_fields;
+        self
+    }
+
+    // the targeted values of the fields in the schema are typed, and the keywords which are not text are searched by `term`,
+    // the values of the other fields are searched as text like without the schema
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn build(&self, condition: &Condition) -> Value {
+        match condition {
+            Condition::None => json!({ "match_all": {} }),
+            Condition::Keyword(_)
+            | Condition::PhraseKeyword(_)
+            | Condition::Prefix(_)
+            | Condition::Wildcard(_)
+            | Condition::Fuzzy { .. }
+            | Condition::Regex(_)
+            | Condition::Proximity { .. }
+            | Condition::Range { .. }
+            | Condition::Comparison { .. }
+            | Condition::Exists { .. } => {
+                self.build_on_target(&condition.clone().identify_target())
+            }
+            Condition::Not(condition) => bool_query(vec![], vec![self.build(condition)]),
+            Condition::Operator(Operator::Near(_) | Operator::OrderedNear(_), _) => {
+                self.build_on_target(&condition.clone().identify_target())
+            }
+            Condition::Operator(operator, conditions) => operator_query(
+                operator,
+                conditions,
+                |condition| match condition {
+                    Condition::Not(condition) => Some(condition.as_ref()),
+                    _ => None,
+                },
+                |condition| self.build(condition),
+            ),
+            Condition::Targeted(..) => self.build_on_target(&condition.clone().identify_target()),
+            Condition::Boolean(clauses) => occur_query(clauses, |condition| self.build(condition)),
+            Condition::Boosted { condition, boost } => {
+                with_boost(self.build(condition), boost.value())
+            }
+        }
+    }
+
+    pub fn build_on_target(&self, condition: &ConditionOnTarget) -> Value {
+        match condition {
+            ConditionOnTarget::None => json!({ "match_all": {} }),
+            ConditionOnTarget::Keyword {
+                condition,
+                target: Some(target),
+            } if self
+                .typed_value(condition, Some(target))
+                .is_some_and(|value| value.value_type() != ValueType::Text) =>
+            {
+                self.term_query(condition, target)
+            }
+            ConditionOnTarget::Keyword { condition, target } => {
+                self.text_query("match", condition, None, target.as_ref())
+            }
+            ConditionOnTarget::PhraseKeyword { condition, target } => {
+                self.text_query("match_phrase", condition, None, target.as_ref())
+            }
+            ConditionOnTarget::Prefix { condition, target } => self.term_level_query(
+                "prefix",
+                condition,
+                &format!("{}*", escape_query_string(condition, &[])),
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Wildcard { condition, target } => {
+                // the wildcard keeps the escapes of `*`, `?` and `\`
+                self.term_level_query(
+                    "wildcard",
+                    condition,
+                    &escape_query_string(condition, &['*', '?', '\\']),
+                    target.as_ref(),
+                )
+            }
+            // only `/` is escaped in the pattern, the other characters are the syntax of the regular expression
+            ConditionOnTarget::Regex { condition, target } => self.term_level_query(
+                "regexp",
+                condition,
+                &format!("/{}/", condition.replace('/', "\\/")),
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Fuzzy {
+                condition,
+                max_edits,
+                target,
+            } => self.text_query(
+                "match",
+                condition,
+                Some(("fuzziness", u32::from(*max_edits))),
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Proximity {
+                condition,
+                slop,
+                target,
+            } => self.text_query(
+                "match_phrase",
+                condition,
+                Some(("slop", *slop)),
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Range {
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+                target,
+            } => range_query(
+                [
+                    (if *inclusive_lower { "gte" } else { "gt" }, lower),
+                    (if *inclusive_upper { "lte" } else { "lt" }, upper),
+                ]
+                .into_iter()
+                .filter_map(|(name, bound)| {
+                    bound
+                        .as_deref()
+                        .map(|bound| (name, self.typed_json(bound, target.as_ref())))
+                })
+                .collect(),
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Comparison {
+                comparison,
+                value,
+                target,
+            } => range_query(
+                vec![(
+                    match comparison {
+                        Comparison::GreaterThan => "gt",
+                        Comparison::GreaterThanOrEqual => "gte",
+                        Comparison::LessThan => "lt",
+                        Comparison::LessThanOrEqual => "lte",
+                    },
+                    self.typed_json(value, target.as_ref()),
+                )],
+                target.as_ref(),
+            ),
+            ConditionOnTarget::Exists {
+                target: Some(target),
+            } => json!({ "exists": { "field": target.name() } }),
+            ConditionOnTarget::Exists { target: None } => json!({ "match_all": {} }),
+            ConditionOnTarget::Not { condition, .. } => {
+                bool_query(vec![], vec![self.build_on_target(condition)])
+            }
+            ConditionOnTarget::Operator {
+                operator: Operator::Near(_) | Operator::OrderedNear(_),
+                conditions,
+                target,
+            } => self.near_query(condition, conditions, target.as_ref()),
+            ConditionOnTarget::Operator {
+                operator,
+                conditions,
+                ..
+            } => operator_query(
+                operator,
+                conditions,
+                |condition| match condition {
+                    ConditionOnTarget::Not { condition, .. } => Some(condition.as_ref()),
+                    _ => None,
+                },
+                |condition| self.build_on_target(condition),
+            ),
+            ConditionOnTarget::Boolean { clauses, .. } => {
+                occur_query(clauses, |condition| self.build_on_target(condition))
+            }
+            ConditionOnTarget::Boosted {
+                condition, boost, ..
+            } => with_boost(self.build_on_target(condition), boost.value()),
+        }
+    }
+
+    // the positions of the terms are searched by `intervals` in each field,
+    // and it's the same as `AND` without the fields or with the terms which `intervals` doesn't support
+    fn near_query(
+        &self, condition: &ConditionOnTarget, conditions: &[ConditionOnTarget],
+        target: Option<&Target>,
+    ) -> Value {
+        let fields = match target {
+            Some(target) => vec![target.name()],
+            None => self
+                .default_fields
+                .iter()
+                .map(|field| field.split('^').next().unwrap_or(field))
+                .collect(),
+        };
+        let rule = match condition.has_positions() {
+            true => intervals_rule(condition),
+            false => None,
+        };
+        match (rule, fields.as_slice()) {
+            (Some(rule), [field]) => json!({ "intervals": { *field: rule } }),
+            (Some(rule), fields) if !fields.is_empty() => json!({
+                "bool": {
+                    "should": fields
+                        .iter()
+                        .map(|field| json!({ "intervals": { *field: rule.clone() } }))
+                        .collect::<Vec<Value>>(),
+                    "minimum_should_match": 1
+                }
+            }),
+            _ => operator_query(
+                &Operator::And,
+                conditions,
+                |condition| match condition {
+                    ConditionOnTarget::Not { condition, .. } => Some(condition.as_ref()),
+                    _ => None,
+                },
+                |condition| self.build_on_target(condition),
+            ),
+        }
+    }
+
+    // the parameter is the fuzziness of a fuzzy keyword or the slop of a proximity phrase
+    fn text_query(
+        &self, query_type: &str, text: &str, parameter: Option<(&str, u32)>,
+        target: Option<&Target>,
+    ) -> Value {
+        let mut query = Map::new();
+        query.insert("query".into(), text.into());
+        if let Some((name, value)) = parameter {
+            query.insert(name.into(), value.into());
+        }
+        match (target, self.default_fields.as_slice()) {
+            (Some(target), _) => {
+                if let Some(weight) = target.weight {
+                    query.insert("boost".into(), boost_json(weight));
+                }
+                json!({ query_type: { target.name.as_str(): query } })
+            }
+            (None, [field]) => json!({ query_type: { field.as_str(): query } }),
+            (None, fields) => {
+                if !fields.is_empty() {
+                    query.insert("fields".into(), fields.into());
+                }
+                if query_type == "match_phrase" {
+                    query.insert("type".into(), "phrase".into());
+                }
+                json!({ "multi_match": query })
+            }
+        }
+    }
+
+    fn term_query(&self, value: &str, target: &Target) -> Value {
+        let mut query = Map::new();
+        query.insert("value".into(), self.typed_json(value, Some(target)));
+        if let Some(weight) = target.weight {
+            query.insert("boost".into(), boost_json(weight));
+        }
+        json!({ "term": { target.name.as_str(): query } })
+    }
+
+    // only the targeted values of the fields in the schema, the types are not inferred to keep `zip:01234` as it is,
+    // and the values which can't be read as the type in the schema are left as text
+    fn typed_value(&self, value: &str, target: Option<&Target>) -> Option<TypedValue> {
+        let value_type = self.schema.value_type(target?.name())?;
+        TypedValue::parse(value, value_type)
+    }
+
+    fn typed_json(&self, value: &str, target: Option<&Target>) -> Value {
+        match self.typed_value(value, target) {
+            Some(TypedValue::Integer(integer)) => integer.into(),
+            Some(TypedValue::Float(float)) => float.into(),
+            Some(TypedValue::Bool(bool)) => bool.into(),
+            _ => value.into(),
+        }
+    }
+
+    // `query_string` is used for the keywords without target on multi fields, then the value is written with the wildcards
+    fn term_level_query(
+        &self, query_type: &str, value: &str, query_string: &str, target: Option<&Target>,
+    ) -> Value {
+        match (target, self.default_fields.as_slice()) {
+            (Some(target), _) => {
+                let mut query = Map::new();
+                query.insert("value".into(), value.into());
+                if let Some(weight) = target.weight {
+                    query.insert("boost".into(), boost_json(weight));
+                }
+                json!({ query_type: { target.name.as_str(): query } })
+            }
+            (None, [field]) => json!({ query_type: { field.as_str(): { "value": value } } }),
+            (None, fields) => {
+                let mut query = Map::new();
+                query.insert("query".into(), query_string.into());
+                if !fields.is_empty() {
+                    query.insert("fields".into(), fields.into());
+                }
+                json!({ "query_string": query })
+            }
+        }
+    }
+}
+
+// the characters which have special meanings in `query_string`, the spaces are also escaped
+const QUERY_STRING_RESERVED_CHARS: &str = "\\+-=&|<>!(){}[]^\"~*?:/";
+
+// the value written in `query_string`, the characters in `kept` like the wildcards stay as they are
+fn escape_query_string(value: &str, kept: &[char]) -> String {
+    value
+        .chars()
+        .flat_map(|c| {
+            match (QUERY_STRING_RESERVED_CHARS.contains(c) || c.is_whitespace())
+                && !kept.contains(&c)
+            {
+                true => vec!['\\', c],
+                false => vec![c],
+            }
+        })
+        .collect()
+}
+
+// the bounds are like `("gte", "10")`
+fn range_query(bounds: Vec<(&str, Value)>, target: Option<&Target>) -> Value {
+    let mut query = Map::new();
+    bounds.into_iter().for_each(|(name, bound)| {
+        query.insert(name.into(), bound);
+    });
+    match target {
+        Some(target) => {
+            if let Some(weight) = target.weight {
+                query.insert("boost".into(), boost_json(weight));
+            }
+            json!({ "range": { target.name.as_str(): query } })
+        }
+        // not built by the parsers, the ranges and comparisons always have their targets
+        None => json!({ "match_all": {} }),
+    }
+}
+
+// through the string not to get the f32 rounding error like 0.20000000298023224
+fn boost_json(boost: f32) -> Value {
+    json!(boost.to_string().parse::<f64>().ok())
+}
+
+// the boost is put into the query of the field or the body of the query,
+// and the query is wrapped by a bool query when it can't have the boost or already has the weight of the target
+fn with_boost(mut query: Value, boost: f32) -> Value {
+    let boost = boost_json(boost);
+    let boosted = match query
+        .as_object_mut()
+        .and_then(|query| query.iter_mut().next())
+    {
+        Some((query_type, Value::Object(fields)))
+            if [
+                "match",
+                "match_phrase",
+                "term",
+                "prefix",
+                "wildcard",
+                "regexp",
+                "range",
+            ]
+            .contains(&query_type.as_str()) =>
+        {
+            match fields.values_mut().next() {
+                Some(Value::Object(field)) if !field.contains_key("boost") => {
+                    field.insert("boost".into(), boost.clone());
+                    true
+                }
+                _ => false,
+            }
+        }
+        Some((query_type, Value::Object(body)))
+            if ["bool", "multi_match", "query_string", "exists", "match_all"]
+                .contains(&query_type.as_str())
+                && !body.contains_key("boost") =>
+        {
+            body.insert("boost".into(), boost.clone());
+            true
+        }
+        _ => false,
+    };
+    match boosted {
+        true => query,
+        false => json!({ "bool": { "must": [query], "boost": boost } }),
+    }
+}
+
+// the rule of `intervals` for a term in `NEAR/n`, the phrases are the terms without gaps
+fn intervals_rule(condition: &ConditionOnTarget) -> Option<Value> {
+    let rules = |conditions: &[ConditionOnTarget]| {
+        conditions
+            .iter()
+            .map(intervals_rule)
+            .collect::<Option<Vec<Value>>>()
+    };
+    match condition {
+        ConditionOnTarget::Keyword { condition, .. } => {
+            Some(json!({ "match": { "query": condition } }))
+        }
+        ConditionOnTarget::PhraseKeyword { condition, .. } => Some(json!({
+            "match": { "query": condition, "max_gaps": 0, "ordered": true }
+        })),
+        ConditionOnTarget::Proximity {
+            condition, slop, ..
+        } => Some(json!({
+            "match": { "query": condition, "max_gaps": slop, "ordered": true }
+        })),
+        ConditionOnTarget::Prefix { condition, .. } => {
+            Some(json!({ "prefix": { "prefix": condition } }))
+        }
+        ConditionOnTarget::Wildcard { condition, .. } => {
+            Some(json!({ "wildcard": { "pattern": condition } }))
+        }
+        ConditionOnTarget::Regex { condition, .. } => {
+            Some(json!({ "regexp": { "pattern": condition } }))
+        }
+        ConditionOnTarget::Fuzzy {
+            condition,
+            max_edits,
+            ..
+        } => Some(json!({ "fuzzy": { "term": condition, "fuzziness": max_edits } })),
+        // the rules don't have the boost
+        ConditionOnTarget::Boosted { condition, .. } => intervals_rule(condition),
+        ConditionOnTarget::Operator {
+            operator: Operator::Or,
+            conditions,
+            ..
+        } => Some(json!({ "any_of": { "intervals": rules(conditions)? } })),
+        ConditionOnTarget::Operator {
+            operator,
+            conditions,
+            ..
+        } => {
+            let (distance, ordered) = operator.near()?;
+            Some(json!({
+                "all_of": { "intervals": rules(conditions)?, "max_gaps": distance, "ordered": ordered }
+            }))
+        }
+        _ => None,
+    }
+}
+
+// the negative children of AND are put into `must_not` of the same bool query
+fn operator_query<'a, C: 'a>(
+    operator: &Operator, conditions: &'a [C], negative: impl Fn(&'a C) -> Option<&'a C>,
+    build: impl Fn(&'a C) -> Value,
+) -> Value {
+    match operator {
+        // `NEAR/n` is built by `near_query`, and it's the same as `AND` without the positions
+        Operator::And | Operator::Near(_) | Operator::OrderedNear(_) => {
+            let mut must = Vec::<Value>::new();
+            let mut must_not = Vec::<Value>::new();
+            conditions
+                .iter()
+                .for_each(|condition| match negative(condition) {
+                    Some(condition) => must_not.push(build(condition)),
+                    None => must.push(build(condition)),
+                });
+            bool_query(must, must_not)
+        }
+        Operator::Or => json!({
+            "bool": {
+                "should": conditions.iter().map(build).collect::<Vec<Value>>(),
+                "minimum_should_match": 1
+            }
+        }),
+    }
+}
+
+// elasticsearch also ignores the should clauses when there is a must clause, and requires one of them otherwise
+fn occur_query<'a, C: 'a>(clauses: &'a [(Occur, C)], build: impl Fn(&'a C) -> Value) -> Value {
+    let mut query = Map::new();
+    [
+        (Occur::Must, "must"),
+        (Occur::Should, "should"),
+        (Occur::MustNot, "must_not"),
+    ]
+    .into_iter()
+    .for_each(|(occur, name)| {
+        let values = clauses
+            .iter()
+            .filter(|(clause_occur, _)| clause_occur == &occur)
+            .map(|(_, condition)| build(condition))
+            .collect::<Vec<Value>>();
+        if !values.is_empty() {
+            query.insert(name.into(), values.into());
+        }
+    });
+    json!({ "bool": query })
+}
+
+fn bool_query(must: Vec<Value>, must_not: Vec<Value>) -> Value {
+    let mut query = Map::new();
+    if !must.is_empty() {
+        query.insert("must".into(), must.into());
+    }
+    if !must_not.is_empty() {
+        query.insert("must_not".into(), must_not.into());
+    }
+    json!({ "bool": query })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse_query_to_condition, parse_query_to_condition_on_target,
+        parse_query_to_condition_with, Approach, DefaultOperator, Parser,
+    };
+
+    mod test_build {
+        use super::*;
+
+        #[test]
+        fn test_build_none() {
+            assert_eq!(
+                QueryDsl::new().build(&Condition::None),
+                json!({ "match_all": {} })
+            )
+        }
+
+        #[test]
+        fn test_build_keyword_without_default_fields() {
+            assert_eq!(
+                QueryDsl::new().build(&Condition::Keyword("rust".into())),
+                json!({ "multi_match": { "query": "rust" } })
+            )
+        }
+
+        #[test]
+        fn test_build_keyword_on_one_default_field() {
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title".into()])
+                    .build(&Condition::Keyword("rust".into())),
+                json!({ "match": { "title": { "query": "rust" } } })
+            )
+        }
+
+        #[test]
+        fn test_build_phrase_keyword_on_multi_default_fields() {
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title^2".into(), "body".into()])
+                    .build(&Condition::PhraseKeyword("exact phrase".into())),
+                json!({
+                    "multi_match": {
+                        "query": "exact phrase",
+                        "fields": ["title^2", "body"],
+                        "type": "phrase"
+                    }
+                })
+            )
+        }
+
+        #[test]
+        fn test_build_full_pattern() {
+            let condition = parse_query_to_condition("(A -B) OR -(\"C D\" OR E) OR F").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "bool": {
+                        "should": [
+                            {
+                                "bool": {
+                                    "must": [{ "match": { "body": { "query": "A" } } }],
+                                    "must_not": [{ "match": { "body": { "query": "B" } } }]
+                                }
+                            },
+                            {
+                                "bool": {
+                                    "must_not": [{
+                                        "bool": {
+                                            "should": [
+                                                { "match_phrase": { "body": { "query": "C D" } } },
+                                                { "match": { "body": { "query": "E" } } }
+                                            ],
+                                            "minimum_should_match": 1
+                                        }
+                                    }]
+                                }
+                            },
+                            { "match": { "body": { "query": "F" } } }
+                        ],
+                        "minimum_should_match": 1
+                    }
+                })
+            )
+        }
+
+        #[test]
+        fn test_build_targeted() {
+            let condition = parse_query_to_condition("title^2:\"exact phrase\" rust").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "match_phrase": { "title": { "query": "exact phrase", "boost": 2.0 } } },
+                            { "match": { "body": { "query": "rust" } } }
+                        ]
+                    }
+                })
+            )
+        }
+    }
+
+    mod test_build_on_target {
+        use super::*;
+
+        #[test]
+        fn test_build_on_target_full_pattern() {
+            let condition = parse_query_to_condition_on_target(
+                "title^0.2:(rust OR \"rust lang\") -author:bob go",
+            )
+            .unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title".into(), "body".into()])
+                    .build_on_target(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            {
+                                "bool": {
+                                    "should": [
+                                        { "match": { "title": { "query": "rust", "boost": 0.2 } } },
+                                        { "match_phrase": { "title": { "query": "rust lang", "boost": 0.2 } } }
+                                    ],
+                                    "minimum_should_match": 1
+                                }
+                            },
+                            { "multi_match": { "query": "go", "fields": ["title", "body"] } }
+                        ],
+                        "must_not": [{ "match": { "author": { "query": "bob" } } }]
+                    }
+                })
+            )
+        }
+
+        #[test]
+        fn test_build_on_target_prefix_and_wildcard() {
+            let condition = parse_query_to_condition_on_target("title^2:rust* te?t").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title".into(), "body".into()])
+                    .build_on_target(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "prefix": { "title": { "value": "rust", "boost": 2.0 } } },
+                            { "query_string": { "query": "te?t", "fields": ["title", "body"] } }
+                        ]
+                    }
+                })
+            );
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&Condition::Prefix("ru*st".into())),
+                json!({ "prefix": { "body": { "value": "ru*st" } } })
+            );
+            assert_eq!(
+                QueryDsl::new().build(&Condition::Prefix("ru*st".into())),
+                json!({ "query_string": { "query": "ru\\*st*" } })
+            );
+            // the reserved characters of `query_string` are escaped except the wildcards
+            let condition = parse_query_to_condition("a\\:b*c d\\ e\\*f? g\\(h*").unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "query_string": { "query": "a\\:b*c" } },
+                            { "query_string": { "query": "d\\ e\\*f?" } },
+                            { "query_string": { "query": "g\\(h*" } }
+                        ]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_on_target_fuzzy() {
+            let condition = parse_query_to_condition_on_target("title^2:rust~1 go~").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title".into(), "body".into()])
+                    .build_on_target(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "match": { "title": { "query": "rust", "fuzziness": 1, "boost": 2.0 } } },
+                            { "multi_match": { "query": "go", "fuzziness": 2, "fields": ["title", "body"] } }
+                        ]
+                    }
+                })
+            );
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&Condition::Fuzzy {
+                        term: "rust".into(),
+                        max_edits: 0
+                    }),
+                json!({ "match": { "body": { "query": "rust", "fuzziness": 0 } } })
+            );
+        }
+
+        #[test]
+        fn test_build_on_target_proximity() {
+            let condition =
+                parse_query_to_condition_on_target("title:\"quick fox\"~5 \"lazy dog\"~1").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["title".into(), "body".into()])
+                    .build_on_target(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "match_phrase": { "title": { "query": "quick fox", "slop": 5 } } },
+                            {
+                                "multi_match": {
+                                    "query": "lazy dog",
+                                    "slop": 1,
+                                    "fields": ["title", "body"],
+                                    "type": "phrase"
+                                }
+                            }
+                        ]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_range() {
+            assert_eq!(
+                QueryDsl::new().build(
+                    &parse_query_to_condition("price^2:[10 TO 100] date:{2020-01-01 TO *]")
+                        .unwrap()
+                ),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "range": { "price": { "gte": "10", "lte": "100", "boost": 2.0 } } },
+                            { "range": { "date": { "gt": "2020-01-01" } } }
+                        ]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_comparison() {
+            assert_eq!(
+                QueryDsl::new().build(&parse_query_to_condition("price:>=100 stars:<3").unwrap()),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "range": { "price": { "gte": "100" } } },
+                            { "range": { "stars": { "lt": "3" } } }
+                        ]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_boolean() {
+            let condition = Parser::new()
+                .with_default_operator(DefaultOperator::Or)
+                .parse("+rust go -java")
+                .unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "must": [{ "multi_match": { "query": "rust" } }],
+                        "should": [{ "multi_match": { "query": "go" } }],
+                        "must_not": [{ "multi_match": { "query": "java" } }]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_exists() {
+            let condition = parse_query_to_condition("_exists_:title -_exists_:author").unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "must": [{ "exists": { "field": "title" } }],
+                        "must_not": [{ "exists": { "field": "author" } }]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_regex() {
+            let condition = parse_query_to_condition("/err(or)?[0-9]+/ -log:/a\\/b/").unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "must": [{ "query_string": { "query": "/err(or)?[0-9]+/" } }],
+                        "must_not": [{ "regexp": { "log": { "value": "a/b" } } }]
+                    }
+                })
+            );
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["message".into()])
+                    .build(&Condition::Regex("err(or)?[0-9]+".into())),
+                json!({ "regexp": { "message": { "value": "err(or)?[0-9]+" } } })
+            );
+        }
+
+        #[test]
+        fn test_build_near() {
+            let condition =
+                parse_query_to_condition("contract NEAR/5 \"breach of\" ONEAR/1 duty*").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body^2".into()])
+                    .build(&condition),
+                json!({
+                    "intervals": {
+                        "body": {
+                            "all_of": {
+                                "intervals": [
+                                    {
+                                        "all_of": {
+                                            "intervals": [
+                                                { "match": { "query": "contract" } },
+                                                { "match": { "query": "breach of", "max_gaps": 0, "ordered": true } }
+                                            ],
+                                            "max_gaps": 5,
+                                            "ordered": false
+                                        }
+                                    },
+                                    { "prefix": { "prefix": "duty" } }
+                                ],
+                                "max_gaps": 1,
+                                "ordered": true
+                            }
+                        }
+                    }
+                })
+            );
+            let condition = parse_query_to_condition("title:(a OR b) NEAR/2 c").unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            {
+                                "bool": {
+                                    "should": [
+                                        { "match": { "title": { "query": "a" } } },
+                                        { "match": { "title": { "query": "b" } } }
+                                    ],
+                                    "minimum_should_match": 1
+                                }
+                            },
+                            { "multi_match": { "query": "c" } }
+                        ]
+                    }
+                })
+            );
+            let condition = parse_query_to_condition("title:(a OR b NEAR/2 c)").unwrap();
+            assert_eq!(
+                QueryDsl::new().build(&condition),
+                json!({
+                    "bool": {
+                        "should": [
+                            { "match": { "title": { "query": "a" } } },
+                            {
+                                "intervals": {
+                                    "title": {
+                                        "all_of": {
+                                            "intervals": [
+                                                { "match": { "query": "b" } },
+                                                { "match": { "query": "c" } }
+                                            ],
+                                            "max_gaps": 2,
+                                            "ordered": false
+                                        }
+                                    }
+                                }
+                            }
+                        ],
+                        "minimum_should_match": 1
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_near_with_regex() {
+            let condition = parse_query_to_condition("/ru.t/ NEAR/2 lang*").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "intervals": {
+                        "body": {
+                            "all_of": {
+                                "intervals": [
+                                    { "regexp": { "pattern": "ru.t" } },
+                                    { "prefix": { "prefix": "lang" } }
+                                ],
+                                "max_gaps": 2,
+                                "ordered": false
+                            }
+                        }
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_near_without_positions() {
+            let condition = parse_query_to_condition("a NEAR/3 -b").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "bool": {
+                        "must": [{ "match": { "body": { "query": "a" } } }],
+                        "must_not": [{ "match": { "body": { "query": "b" } } }]
+                    }
+                })
+            );
+        }
+
+        #[test]
+        fn test_build_boosted() {
+            let condition =
+                parse_query_to_condition("rust^2 title^2:\"a b\"^0.5 (c OR -d)^3").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "match": { "body": { "query": "rust", "boost": 2.0 } } },
+                            {
+                                "bool": {
+                                    "must": [
+                                        { "match_phrase": { "title": { "query": "a b", "boost": 2.0 } } }
+                                    ],
+                                    "boost": 0.5
+                                }
+                            },
+                            {
+                                "bool": {
+                                    "should": [
+                                        { "match": { "body": { "query": "c" } } },
+                                        { "bool": { "must_not": [{ "match": { "body": { "query": "d" } } }] } }
+                                    ],
+                                    "minimum_should_match": 1,
+                                    "boost": 3.0
+                                }
+                            }
+                        ]
+                    }
+                })
+            );
+            let condition = parse_query_to_condition("(a NEAR/1 b^2)^0.5").unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_default_fields(vec!["body".into()])
+                    .build(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            {
+                                "intervals": {
+                                    "body": {
+                                        "all_of": {
+                                            "intervals": [
+                                                { "match": { "query": "a" } },
+                                                { "match": { "query": "b" } }
+                                            ],
+                                            "max_gaps": 1,
+                                            "ordered": false
+                                        }
+                                    }
+                                }
+                            }
+                        ],
+                        "boost": 0.5
+                    }
+                })
+            )
+        }
+
+        #[test]
+        fn test_build_keyword_with_target() {
+            // the types are not inferred without the schema, so `zip:01234` keeps the leading zero
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let condition = parse_query_to_condition_with(
+                        "title:rust year:2020 zip:01234 title:2020-01-01 rust",
+                        approach,
+                    )
+                    .unwrap();
+                    assert_eq!(
+                        QueryDsl::new()
+                            .with_default_fields(vec!["body".into()])
+                            .build(&condition),
+                        json!({
+                            "bool": {
+                                "must": [
+                                    { "match": { "title": { "query": "rust" } } },
+                                    { "match": { "year": { "query": "2020" } } },
+                                    { "match": { "zip": { "query": "01234" } } },
+                                    { "match": { "title": { "query": "2020-01-01" } } },
+                                    { "match": { "body": { "query": "rust" } } }
+                                ]
+                            }
+                        }),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_build_with_schema() {
+            let condition = parse_query_to_condition_on_target(
+                "year:2020 active:true zip:01234 title:rust price:[10 TO *] stars:>=4.5",
+            )
+            .unwrap();
+            assert_eq!(
+                QueryDsl::new()
+                    .with_schema(
+                        Schema::new()
+                            .with_field("year", ValueType::Integer)
+                            .with_field("active", ValueType::Bool)
+                            .with_field("zip", ValueType::Text)
+                            .with_field("price", ValueType::Float)
+                    )
+                    .build_on_target(&condition),
+                json!({
+                    "bool": {
+                        "must": [
+                            { "term": { "year": { "value": 2020 } } },
+                            { "term": { "active": { "value": true } } },
+                            { "match": { "zip": { "query": "01234" } } },
+                            { "match": { "title": { "query": "rust" } } },
+                            { "range": { "price": { "gte": 10.0 } } },
+                            { "range": { "stars": { "gte": "4.5" } } }
+                        ]
+                    }
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use crate::term::SPECIAL_CHARS;
+use crate::Span;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// the characters which have special meanings in a query, the spaces are also escaped
+const RESERVED_CHARS: &str = "\\()（）\"”-+!！:：*?~～&＆|｜[]{}<>＜＞=＝^/／";
+
+pub(crate) const COLONS: [char; 2] = [':', '：'];
+
+static REGEX_OPERATOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("^(?i)(?:[AＡ][NＮ][DＤ]|[OＯ][RＲ]|[NＮ][OＯ][TＴ])$").unwrap());
+
+// the user text is searched as it is, when it's embedded in a query like `format!("title:{}", escape_term(text))`
+pub fn escape_term(term: &str) -> String {
+    escape_keyword(term, &[])
+}
+
+// to write the keywords, the targets and the bounds back to the query,
+// the characters in `kept` like `*` of a wildcard stay as they are, and so do the escapes when `\` is kept
+pub(crate) fn escape_keyword(keyword: &str, kept: &[char]) -> String {
+    escape_operator(escape_chars(keyword, kept, is_reserved))
+}
+
+// the double quotations and backslashes in a phrase
+pub(crate) fn escape_phrase(phrase: &str) -> String {
+    escape_chars(phrase, &[], |c| matches!(c, '"' | '”' | '\\'))
+}
+
+fn escape_chars(text: &str, kept: &[char], is_escaped: fn(char) -> bool) -> String {
+    let mut escaped = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if kept.contains(&c) => {
+                escaped.push(c);
+                escaped.push(next);
+                chars.next();
+            }
+            (c, _) if is_escaped(c) && !kept.contains(&c) => {
+                escaped.push('\\');
+                escaped.push(c)
+            }
+            (c, _) => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn is_reserved(c: char) -> bool {
+    RESERVED_CHARS.contains(c) || c.is_whitespace()
+}
+
+// `AND`, `OR` and `NOT` are searched as keywords when their first character is escaped
+fn escape_operator(escaped: String) -> String {
+    match REGEX_OPERATOR.is_match(&escaped) {
+        true => format!("\\{}", escaped),
+        false => escaped,
+    }
+}
+
+// the query whose escaped characters and regex patterns are replaced with the placeholders,
+// with the offsets in the original query for the spans
+pub(crate) struct EscapedQuery {
+    pub(crate) query: String,
+    pub(crate) escapes: Escapes,
+    offsets: Vec<usize>,
+    placeholders: HashMap<String, char>,
+    // the characters typed in the query can't be the placeholders
+    typed: HashSet<char>,
+    last_placeholder: Option<char>,
+}
+
+// the placeholders are the private use characters which are not in the query, and each of them is restored
+// to the escaped text like `\(` or the pattern of `/pattern/` before the keywords are parsed
+#[derive(Debug, Default)]
+pub(crate) struct Escapes(HashMap<char, String>);
+
+impl Escapes {
+    pub(crate) fn restore(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| match self.0.get(&c) {
+                Some(escaped) => escaped.clone(),
+                None => c.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl EscapedQuery {
+    // `\*`, `\?` and `\~` are left as they are for the wildcards and fuzzy keywords,
+    // and a backslash at the end is a backslash
+    pub(crate) fn new(query: &str) -> Self {
+        let mut escaped = Self {
+            query: String::new(),
+            escapes: Escapes::default(),
+            offsets: vec![],
+            placeholders: HashMap::new(),
+            typed: query.chars().collect(),
+            last_placeholder: None,
+        };
+        let chars = query.char_indices().collect::<Vec<(usize, char)>>();
+        let mut in_phrase = false;
+        let mut k = 0;
+        while let Some(&(i, c)) = chars.get(k) {
+            match (c, chars.get(k + 1)) {
+                ('\\', Some((_, next))) if !SPECIAL_CHARS.contains(next) => {
+                    escaped.push_escaped(&chars[k..k + 2]);
+                    k += 2
+                }
+                ('/', _) if !in_phrase && is_word_start(k.checked_sub(1).map(|k| chars[k].1)) => {
+                    match regex_len(&chars[k..]) {
+                        Some(len) => {
+                            // the pattern is escaped as a whole, so the brackets and spaces in it are not the symbols
+                            escaped.push(i, c);
+                            escaped.push_escaped(&chars[k + 1..k + len - 1]);
+                            escaped.push(chars[k + len - 1].0, '/');
+                            k += len
+                        }
+                        None => {
+                            escaped.push(i, c);
+                            k += 1
+                        }
+                    }
+                }
+                (c, _) => {
+                    in_phrase ^= matches!(c, '"' | '”');
+                    escaped.push(i, c);
+                    k += 1
+                }
+            }
+        }
+        escaped.offsets.push(query.len());
+        escaped
+    }
+
+    fn push(&mut self, offset: usize, c: char) {
+        self.offsets.extend(vec![offset; c.len_utf8()]);
+        self.query.push(c)
+    }
+
+    // the same text shares the placeholder, and the text is left as it is when they run out
+    fn push_escaped(&mut self, chars: &[(usize, char)]) {
+        let text = chars.iter().map(|(_, c)| c).collect::<String>();
+        let placeholder = match self.placeholders.get(&text) {
+            Some(placeholder) => Some(*placeholder),
+            None => self.next_placeholder(),
+        };
+        match placeholder {
+            Some(placeholder) => {
+                self.placeholders.insert(text.clone(), placeholder);
+                self.escapes.0.insert(placeholder, text);
+                self.push(chars[0].0, placeholder)
+            }
+            None => chars.iter().for_each(|(i, c)| self.push(*i, *c)),
+        }
+    }
+
+    fn next_placeholder(&mut self) -> Option<char> {
+        let mut code = self.last_placeholder.map_or(0xE000, |c| c as u32 + 1);
+        loop {
+            code = match code {
+                0xF900 => 0xF0000,
+                0xFFFFE => 0x100000,
+                0x10FFFE => return None,
+                code => code,
+            };
+            match char::from_u32(code).filter(|c| !self.typed.contains(c)) {
+                Some(placeholder) => {
+                    self.last_placeholder = Some(placeholder);
+                    return Some(placeholder);
+                }
+                None => code += 1,
+            }
+        }
+    }
+
+    pub(crate) fn original_span(&self, span: Span) -> Span {
+        Span {
+            start: self.offsets[span.start],
+            end: self.offsets[span.end],
+        }
+    }
+}
+
+// `/` after a space, a bracket, a sign or a target starts a regex term
+fn is_word_start(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "(（-+!！:：".contains(c))
+}
+
+// the length of `/pattern/` with the slashes, the pattern is not empty
+fn regex_len(chars: &[(usize, char)]) -> Option<usize> {
+    let mut k = 1;
+    while let Some((_, c)) = chars.get(k) {
+        match c {
+            '\\' => k += 2,
+            '/' if k > 1 => return Some(k + 1),
+            '/' => return None,
+            _ => k += 1,
+        }
+    }
+    None
+}
+
+// the escaped characters are restored, the ones in `kept` stay escaped, and a backslash at the end is a backslash
+pub(crate) fn unescape(value: &str, kept: &[char]) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some(next) if kept.contains(&next) => {
+                    unescaped.push(c);
+                    unescaped.push(next)
+                }
+                Some(next) => unescaped.push(next),
+                None => unescaped.push(c),
+            },
+            (c, false) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod test_escape {
+        use super::*;
+
+        #[test]
+        fn test_escape_term() {
+            vec![
+                ("rust", "rust"),
+                ("(a) \"b\"", "\\(a\\)\\ \\\"b\\\""),
+                ("-c:d*", "\\-c\\:d\\*"),
+                ("C:\\path", "C\\:\\\\path"),
+                ("OR", "\\OR"),
+                ("ｎｏｔ", "\\ｎｏｔ"),
+                ("ORDER", "ORDER"),
+                ("NEAR/2", "NEAR\\/2"),
+                ("ＮＥＡＲ／２", "ＮＥＡＲ\\／２"),
+            ]
+            .into_iter()
+            .for_each(|(term, expected)| assert_eq!(escape_term(term), expected, "{}", term))
+        }
+
+        #[test]
+        fn test_escaped_query() {
+            let escaped = EscapedQuery::new("\\(a\\) b\\* \\");
+            assert_eq!(escaped.query.split(' ').count(), 3);
+            assert_eq!(escaped.escapes.restore(&escaped.query), "\\(a\\) b\\* \\");
+            let end = escaped.query.find(' ').unwrap();
+            assert_eq!(
+                escaped.original_span(Span { start: 0, end }),
+                Span { start: 0, end: 5 }
+            );
+        }
+
+        #[test]
+        fn test_escaped_regex() {
+            let escaped = EscapedQuery::new("/a (b)\\/c\\d/ x/y/ \"/z/\"");
+            assert_eq!(escaped.query.split(' ').count(), 3);
+            assert_eq!(
+                escaped.escapes.restore(&escaped.query),
+                "/a (b)\\/c\\d/ x/y/ \"/z/\""
+            );
+            let end = escaped.query.find(' ').unwrap();
+            assert_eq!(
+                escaped.original_span(Span { start: 0, end }),
+                Span { start: 0, end: 12 }
+            );
+        }
+
+        #[test]
+        fn test_escaped_query_with_private_use_characters() {
+            let query = "\u{E000}\\(\u{F0028}\\) \u{100041}\\:";
+            let escaped = EscapedQuery::new(query);
+            assert!(!escaped.query.contains(['(', ')', ':']));
+            assert_eq!(escaped.escapes.restore(&escaped.query), query);
+        }
+
+        #[test]
+        fn test_unescape() {
+            vec![
+                ("a\\:b\\-c", "a:b-c"),
+                ("\\\\", "\\"),
+                ("a\\", "a\\"),
+                ("\\\\\\:", "\\:"),
+            ]
+            .into_iter()
+            .for_each(|(value, expected)| assert_eq!(unescape(value, &[]), expected, "{}", value));
+            assert_eq!(unescape("a\\:b\\-c", &COLONS), "a\\:b-c");
+        }
+    }
+}
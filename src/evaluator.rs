@@ -0,0 +1,975 @@
+use crate::{Comparison, Condition, ConditionOnTarget, Occur, Operator, Target};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+pub trait Document {
+    // the text of the field searched by the conditions with target
+    fn field(&self, name: &str) -> Option<&str>;
+
+    // the texts searched by the conditions without target
+    fn texts(&self) -> Vec<&str>;
+}
+
+impl Document for str {
+    fn field(&self, _: &str) -> Option<&str> {
+        None
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        vec![self]
+    }
+}
+
+impl Document for String {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.as_str().field(name)
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        self.as_str().texts()
+    }
+}
+
+impl<S: AsRef<str>> Document for HashMap<String, S> {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.get(name).map(|text| text.as_ref())
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        self.values().map(|text| text.as_ref()).collect()
+    }
+}
+
+// both the keywords and the texts of the documents are tokenized by it
+pub trait TextTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+// splits the text by the characters other than letters and digits, and ignores the case
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimpleTokenizer;
+
+impl TextTokenizer for SimpleTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect()
+    }
+}
+
+// for the languages without spaces between words like Japanese,
+// the words split by `SimpleTokenizer` are tokenized again into every two characters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BigramTokenizer;
+
+impl TextTokenizer for BigramTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        SimpleTokenizer
+            .tokenize(text)
+            .into_iter()
+            .flat_map(|word| {
+                let chars = word.chars().collect::<Vec<char>>();
+                match chars.len() {
+                    1 => vec![word],
+                    _ => chars
+                        .windows(2)
+                        .map(|bigram| bigram.iter().collect())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+// a reference to a tokenizer is also a tokenizer, for `matches_with`
+impl<T: TextTokenizer + ?Sized> TextTokenizer for &T {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        (*self).tokenize(text)
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, doc: &(impl Document + ?Sized)) -> bool {
+        self.matches_with(doc, &SimpleTokenizer)
+    }
+
+    // the targets in the condition are identified first, `title:rust` searches `rust` in the field `title`
+    pub fn matches_with(
+        &self, doc: &(impl Document + ?Sized), tokenizer: &impl TextTokenizer,
+    ) -> bool {
+        self.matcher_with(tokenizer).matches(doc)
+    }
+
+    pub fn matcher(&self) -> Matcher {
+        self.matcher_with(SimpleTokenizer)
+    }
+
+    pub fn matcher_with<T: TextTokenizer>(&self, tokenizer: T) -> Matcher<T> {
+        self.clone().identify_target().matcher_with(tokenizer)
+    }
+}
+
+impl ConditionOnTarget {
+    pub fn matches(&self, doc: &(impl Document + ?Sized)) -> bool {
+        self.matches_with(doc, &SimpleTokenizer)
+    }
+
+    pub fn matches_with(
+        &self, doc: &(impl Document + ?Sized), tokenizer: &impl TextTokenizer,
+    ) -> bool {
+        self.matcher_with(tokenizer).matches(doc)
+    }
+
+    pub fn matcher(&self) -> Matcher {
+        self.matcher_with(SimpleTokenizer)
+    }
+
+    pub fn matcher_with<T: TextTokenizer>(&self, tokenizer: T) -> Matcher<T> {
+        Matcher {
+            condition: Prepared::new(self, &tokenizer),
+            tokenizer,
+        }
+    }
+}
+
+// the condition prepared once to match many documents,
+// the terms are tokenized and the patterns are compiled only when it's made
+#[derive(Debug, Clone)]
+pub struct Matcher<T: TextTokenizer = SimpleTokenizer> {
+    condition: Prepared,
+    tokenizer: T,
+}
+
+impl<T: TextTokenizer> Matcher<T> {
+    pub fn matches(&self, doc: &(impl Document + ?Sized)) -> bool {
+        self.condition.matches(doc, &self.tokenizer)
+    }
+}
+
+// `ConditionOnTarget` with the tokens of the terms and the compiled patterns, the boosts are dropped
+#[derive(Debug, Clone)]
+enum Prepared {
+    None,
+    Keyword {
+        tokens: Vec<String>,
+        target: Option<Target>,
+    },
+    PhraseKeyword {
+        tokens: Vec<String>,
+        target: Option<Target>,
+    },
+    Proximity {
+        tokens: Vec<String>,
+        slop: usize,
+        target: Option<Target>,
+    },
+    Prefix {
+        tokens: Vec<String>,
+        target: Option<Target>,
+    },
+    Fuzzy {
+        tokens: Vec<String>,
+        max_edits: usize,
+        target: Option<Target>,
+    },
+    // a wildcard or a regular expression, which matches a whole token, `None` when it's invalid
+    Pattern {
+        regex: Option<Regex>,
+        target: Option<Target>,
+    },
+    // the range, comparison and exists, which are matched with the whole text of the field
+    Value(ConditionOnTarget),
+    Not(Box<Prepared>),
+    And(Vec<Prepared>),
+    Or(Vec<Prepared>),
+    Near {
+        distance: usize,
+        ordered: bool,
+        conditions: Vec<Prepared>,
+        target: Option<Target>,
+    },
+    Boolean(Vec<(Occur, Prepared)>),
+}
+
+impl Prepared {
+    fn new(condition: &ConditionOnTarget, tokenizer: &impl TextTokenizer) -> Self {
+        let prepare_all = |conditions: &[ConditionOnTarget]| {
+            conditions
+                .iter()
+                .map(|condition| Self::new(condition, tokenizer))
+                .collect()
+        };
+        match condition {
+            ConditionOnTarget::None => Prepared::None,
+            ConditionOnTarget::Keyword { condition, target } => Prepared::Keyword {
+                tokens: tokenizer.tokenize(condition),
+                target: target.clone(),
+            },
+            ConditionOnTarget::PhraseKeyword { condition, target } => Prepared::PhraseKeyword {
+                tokens: tokenizer.tokenize(condition),
+                target: target.clone(),
+            },
+            ConditionOnTarget::Proximity {
+                condition,
+                slop,
+                target,
+            } => Prepared::Proximity {
+                tokens: tokenizer.tokenize(condition),
+                slop: *slop as usize,
+                target: target.clone(),
+            },
+            ConditionOnTarget::Prefix { condition, target } => Prepared::Prefix {
+                tokens: tokenizer.tokenize(condition),
+                target: target.clone(),
+            },
+            ConditionOnTarget::Fuzzy {
+                condition,
+                max_edits,
+                target,
+            } => Prepared::Fuzzy {
+                tokens: tokenizer.tokenize(condition),
+                max_edits: *max_edits as usize,
+                target: target.clone(),
+            },
+            ConditionOnTarget::Wildcard { condition, target } => Prepared::Pattern {
+                regex: wildcard_regex(condition),
+                target: target.clone(),
+            },
+            // ignoring the case like the tokens
+            ConditionOnTarget::Regex { condition, target } => Prepared::Pattern {
+                regex: Regex::new(&format!("(?i)^(?:{})$", condition)).ok(),
+                target: target.clone(),
+            },
+            ConditionOnTarget::Range { .. }
+            | ConditionOnTarget::Comparison { .. }
+            | ConditionOnTarget::Exists { .. } => Prepared::Value(condition.clone()),
+            ConditionOnTarget::Not { condition, .. } => {
+                Prepared::Not(Box::new(Self::new(condition, tokenizer)))
+            }
+            // the boost changes only the score, not whether it matches
+            ConditionOnTarget::Boosted { condition, .. } => Self::new(condition, tokenizer),
+            ConditionOnTarget::Operator {
+                operator,
+                conditions,
+                target,
+            } => match (operator, operator.near()) {
+                (_, Some((distance, ordered))) if condition.has_positions() => Prepared::Near {
+                    distance: distance as usize,
+                    ordered,
+                    conditions: prepare_all(conditions),
+                    target: target.clone(),
+                },
+                (Operator::Or, _) => Prepared::Or(prepare_all(conditions)),
+                _ => Prepared::And(prepare_all(conditions)),
+            },
+            ConditionOnTarget::Boolean { clauses, .. } => Prepared::Boolean(
+                clauses
+                    .iter()
+                    .map(|(occur, condition)| (*occur, Self::new(condition, tokenizer)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn matches(&self, doc: &(impl Document + ?Sized), tokenizer: &impl TextTokenizer) -> bool {
+        match self {
+            Prepared::None => true,
+            // all the tokens of the keyword are in the text
+            Prepared::Keyword {
+                tokens: keyword_tokens,
+                target,
+            } => matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                !keyword_tokens.is_empty()
+                    && keyword_tokens.iter().all(|token| tokens.contains(token))
+            }),
+            // the tokens of the phrase keyword are in the text in the same order without any gap
+            Prepared::PhraseKeyword {
+                tokens: phrase_tokens,
+                target,
+            } => matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                !phrase_tokens.is_empty()
+                    && tokens
+                        .windows(phrase_tokens.len())
+                        .any(|window| window == phrase_tokens.as_slice())
+            }),
+            // the tokens of the phrase are in the text in the same order with at most `slop` tokens between them
+            Prepared::Proximity {
+                tokens: phrase_tokens,
+                slop,
+                target,
+            } => matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                !phrase_tokens.is_empty()
+                    && (0..tokens.len()).any(|start| {
+                        gap_in_order(&tokens[start..], phrase_tokens)
+                            .is_some_and(|(gap, _)| gap <= *slop)
+                    })
+            }),
+            // the last token of the prefix is the beginning of a token in the text
+            Prepared::Prefix {
+                tokens: prefix_tokens,
+                target,
+            } => matches_text(
+                doc,
+                target.as_ref(),
+                tokenizer,
+                |tokens| match prefix_tokens.split_last() {
+                    Some((last, others)) => {
+                        others.iter().all(|token| tokens.contains(token))
+                            && tokens.iter().any(|token| token.starts_with(last.as_str()))
+                    }
+                    None => false,
+                },
+            ),
+            // the pattern is not tokenized, and it matches a whole token in the text
+            Prepared::Pattern { regex, target } => match regex {
+                Some(regex) => matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                    tokens.iter().any(|token| regex.is_match(token))
+                }),
+                None => false,
+            },
+            // every token of the term is in the text with at most the max edits
+            Prepared::Fuzzy {
+                tokens: term_tokens,
+                max_edits,
+                target,
+            } => matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                !term_tokens.is_empty()
+                    && term_tokens.iter().all(|term_token| {
+                        tokens
+                            .iter()
+                            .any(|token| edit_distance(term_token, token) <= *max_edits)
+                    })
+            }),
+            Prepared::Value(condition) => matches_value(condition, doc),
+            Prepared::Not(condition) => !condition.matches(doc, tokenizer),
+            Prepared::And(conditions) => conditions
+                .iter()
+                .all(|condition| condition.matches(doc, tokenizer)),
+            Prepared::Or(conditions) => conditions
+                .iter()
+                .any(|condition| condition.matches(doc, tokenizer)),
+            // the terms are searched in the same text of the target of `NEAR/n`
+            Prepared::Near { target, .. } => {
+                matches_text(doc, target.as_ref(), tokenizer, |tokens| {
+                    !self.positions(tokens).is_empty()
+                })
+            }
+            // one of the should clauses is required only when there is no must clause
+            Prepared::Boolean(clauses) => {
+                let matches = |occur: Occur| {
+                    clauses
+                        .iter()
+                        .filter(move |(clause_occur, _)| clause_occur == &occur)
+                        .map(|(_, condition)| condition.matches(doc, tokenizer))
+                };
+                let has_must = clauses.iter().any(|(occur, _)| occur == &Occur::Must);
+                matches(Occur::Must).all(|matched| matched)
+                    && !matches(Occur::MustNot).any(|matched| matched)
+                    && (has_must
+                        || clauses.iter().all(|(occur, _)| occur != &Occur::Should)
+                        || matches(Occur::Should).any(|matched| matched))
+            }
+        }
+    }
+
+    // the positions `(start, end)` of the tokens matched by the condition in `NEAR/n`
+    fn positions(&self, tokens: &[String]) -> Vec<(usize, usize)> {
+        fn equals(term: &String) -> TokenMatcher<'_> {
+            Box::new(move |token| token == term)
+        }
+        let matchers: Vec<TokenMatcher> = match self {
+            Prepared::Keyword { tokens: terms, .. }
+            | Prepared::PhraseKeyword { tokens: terms, .. } => terms.iter().map(equals).collect(),
+            Prepared::Prefix { tokens: terms, .. } => match terms.split_last() {
+                Some((last, others)) => {
+                    let mut matchers = others.iter().map(equals).collect::<Vec<_>>();
+                    matchers.push(Box::new(move |token| token.starts_with(last.as_str())));
+                    matchers
+                }
+                None => vec![],
+            },
+            Prepared::Fuzzy {
+                tokens: terms,
+                max_edits,
+                ..
+            } => terms
+                .iter()
+                .map(|term| -> TokenMatcher {
+                    Box::new(move |token| edit_distance(term, token) <= *max_edits)
+                })
+                .collect(),
+            Prepared::Pattern { regex, .. } => regex
+                .iter()
+                .map(|regex| -> TokenMatcher { Box::new(move |token| regex.is_match(token)) })
+                .collect(),
+            Prepared::Proximity {
+                tokens: phrase_tokens,
+                slop,
+                ..
+            } => {
+                return (0..tokens.len())
+                    .filter_map(|start| {
+                        gap_in_order(&tokens[start..], phrase_tokens)
+                            .filter(|(gap, _)| gap <= slop)
+                            .map(|(_, len)| (start, start + len))
+                    })
+                    .collect();
+            }
+            Prepared::Or(conditions) => {
+                let mut positions = conditions
+                    .iter()
+                    .flat_map(|condition| condition.positions(tokens))
+                    .collect::<Vec<(usize, usize)>>();
+                positions.sort();
+                positions.dedup();
+                return positions;
+            }
+            Prepared::Near {
+                distance,
+                ordered,
+                conditions,
+                ..
+            } => {
+                let children = conditions
+                    .iter()
+                    .map(|condition| condition.positions(tokens))
+                    .collect::<Vec<Vec<(usize, usize)>>>();
+                let mut windows = near_windows(&children, *distance, *ordered);
+                windows.sort();
+                windows.dedup();
+                return windows;
+            }
+            _ => vec![],
+        };
+        match matchers.len() {
+            0 => vec![],
+            len => (0..tokens.len())
+                .filter(|start| {
+                    start + len <= tokens.len()
+                        && matchers
+                            .iter()
+                            .zip(&tokens[*start..])
+                            .all(|(matches, token)| matches(token))
+                })
+                .map(|start| (start, start + len))
+                .collect(),
+        }
+    }
+}
+
+// the whole text of the field is compared, as numbers when both are numbers
+fn matches_value(condition: &ConditionOnTarget, doc: &(impl Document + ?Sized)) -> bool {
+    let matches = |target: &Option<Target>, matches_text: &dyn Fn(&str) -> bool| match target {
+        Some(target) => doc.field(target.name()).is_some_and(matches_text),
+        None => doc.texts().into_iter().any(matches_text),
+    };
+    match condition {
+        ConditionOnTarget::Range {
+            lower,
+            upper,
+            inclusive_lower,
+            inclusive_upper,
+            target,
+        } => matches(target, &|text: &str| {
+            let text = text.trim();
+            lower.as_ref().is_none_or(|lower| {
+                matches!(
+                    (compare(text, lower), inclusive_lower),
+                    (Ordering::Greater, _) | (Ordering::Equal, true)
+                )
+            }) && upper.as_ref().is_none_or(|upper| {
+                matches!(
+                    (compare(text, upper), inclusive_upper),
+                    (Ordering::Less, _) | (Ordering::Equal, true)
+                )
+            })
+        }),
+        ConditionOnTarget::Comparison {
+            comparison,
+            value,
+            target,
+        } => matches(target, &|text: &str| {
+            matches!(
+                (comparison, compare(text.trim(), value)),
+                (Comparison::GreaterThan, Ordering::Greater)
+                    | (
+                        Comparison::GreaterThanOrEqual,
+                        Ordering::Greater | Ordering::Equal
+                    )
+                    | (Comparison::LessThan, Ordering::Less)
+                    | (
+                        Comparison::LessThanOrEqual,
+                        Ordering::Less | Ordering::Equal
+                    )
+            )
+        }),
+        // the field is in the document and it's not blank
+        ConditionOnTarget::Exists { target } => target.as_ref().is_some_and(|target| {
+            doc.field(target.name())
+                .is_some_and(|text| !text.trim().is_empty())
+        }),
+        _ => false,
+    }
+}
+
+// the documents without the target field don't match
+fn matches_text(
+    doc: &(impl Document + ?Sized), target: Option<&Target>, tokenizer: &impl TextTokenizer,
+    matches_tokens: impl Fn(&[String]) -> bool,
+) -> bool {
+    match target {
+        Some(target) => doc
+            .field(target.name())
+            .is_some_and(|text| matches_tokens(&tokenizer.tokenize(text))),
+        None => doc
+            .texts()
+            .into_iter()
+            .any(|text| matches_tokens(&tokenizer.tokenize(text))),
+    }
+}
+
+fn wildcard_regex(wildcard: &str) -> Option<Regex> {
+    let mut pattern = String::from("^");
+    let mut escaped = false;
+    for c in wildcard.to_lowercase().chars() {
+        match (escaped, c) {
+            (false, '\\') => {
+                escaped = true;
+                continue;
+            }
+            (false, '*') => pattern.push_str(".*"),
+            (false, '?') => pattern.push('.'),
+            (_, c) => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+        escaped = false;
+    }
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+fn compare(text: &str, bound: &str) -> Ordering {
+    text.parse::<f64>()
+        .ok()
+        .zip(bound.parse::<f64>().ok())
+        .and_then(|(text, bound)| text.partial_cmp(&bound))
+        .unwrap_or_else(|| text.cmp(bound))
+}
+
+// whether a token is matched by a term of the condition
+type TokenMatcher<'a> = Box<dyn Fn(&str) -> bool + 'a>;
+
+// the windows of `NEAR/n` merged from the sorted positions of the children like the intervals of lucene,
+// the unordered one moves the child which ends first, and the ordered one takes the next position which ends first
+fn near_windows(
+    children: &[Vec<(usize, usize)>], distance: usize, ordered: bool,
+) -> Vec<(usize, usize)> {
+    // the positions don't overlap and the number of the tokens between them is at most the distance
+    let window = |picked: &[(usize, usize)]| {
+        let mut sorted = picked.to_vec();
+        sorted.sort();
+        let gap = sorted
+            .windows(2)
+            .map(|pair| pair[1].0.checked_sub(pair[0].1))
+            .sum::<Option<usize>>()?;
+        match (sorted.first(), sorted.last()) {
+            (Some(first), Some(last)) if gap <= distance => Some((first.0, last.1)),
+            _ => None,
+        }
+    };
+    if children.iter().any(|positions| positions.is_empty()) {
+        return vec![];
+    }
+    let mut windows = vec![];
+    match ordered {
+        true => {
+            // the position which ends first in the ones starting at or after each index
+            let first_ends = children
+                .iter()
+                .map(|positions| {
+                    let mut first_ends = positions.clone();
+                    for index in (0..positions.len().saturating_sub(1)).rev() {
+                        if first_ends[index + 1].1 < first_ends[index].1 {
+                            first_ends[index] = first_ends[index + 1];
+                        }
+                    }
+                    first_ends
+                })
+                .collect::<Vec<Vec<(usize, usize)>>>();
+            for first in &children[0] {
+                let picked = children[1..].iter().zip(&first_ends[1..]).try_fold(
+                    vec![*first],
+                    |mut picked, (positions, first_ends)| {
+                        let end = picked.last().map_or(0, |last: &(usize, usize)| last.1);
+                        let next = positions.partition_point(|position| position.0 < end);
+                        picked.push(*first_ends.get(next)?);
+                        Some(picked)
+                    },
+                );
+                windows.extend(picked.and_then(|picked| window(&picked)));
+            }
+        }
+        false => {
+            let mut indexes = vec![0; children.len()];
+            loop {
+                let picked = children
+                    .iter()
+                    .zip(&indexes)
+                    .map(|(positions, index)| positions[*index])
+                    .collect::<Vec<(usize, usize)>>();
+                windows.extend(window(&picked));
+                let first = (0..picked.len())
+                    .min_by_key(|child| (picked[*child].1, picked[*child].0))
+                    .unwrap_or(0);
+                indexes[first] += 1;
+                if indexes[first] == children[first].len() {
+                    break;
+                }
+            }
+        }
+    }
+    windows
+}
+
+// the number of the tokens between the phrase tokens found in order from the first token
+// and the number of the tokens from the first one to the last one,
+// the nearest one is taken for each phrase token
+fn gap_in_order(tokens: &[String], phrase_tokens: &[String]) -> Option<(usize, usize)> {
+    let (first, others) = phrase_tokens.split_first()?;
+    if tokens.first() != Some(first) {
+        return None;
+    }
+    let mut position = 0;
+    let mut gap = 0;
+    for phrase_token in others {
+        let next = tokens[position + 1..]
+            .iter()
+            .position(|token| token == phrase_token)?;
+        gap += next;
+        position += next + 1;
+    }
+    Some((gap, position + 1))
+}
+
+// the levenshtein distance by characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut distances = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut previous = distances[0];
+        distances[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substituted = previous + usize::from(a != *b);
+            previous = distances[j + 1];
+            distances[j + 1] = substituted.min(distances[j] + 1).min(previous + 1);
+        }
+    }
+    distances[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_query_to_condition, DefaultOperator, Parser};
+
+    fn matches(query: &str, doc: &(impl Document + ?Sized)) -> bool {
+        parse_query_to_condition(query).unwrap().matches(doc)
+    }
+
+    fn article() -> HashMap<String, String> {
+        HashMap::from([
+            (
+                "title".to_string(),
+                "The Rust Programming Language".to_string(),
+            ),
+            ("author".to_string(), "Steve Klabnik".to_string()),
+        ])
+    }
+
+    mod test_tokenize {
+        use super::*;
+
+        #[test]
+        fn test_simple_tokenizer() {
+            assert_eq!(
+                SimpleTokenizer.tokenize("Hello, ＷＯＲＬＤ!　search-query"),
+                vec!["hello", "ｗｏｒｌｄ", "search", "query"]
+            )
+        }
+
+        #[test]
+        fn test_bigram_tokenizer() {
+            assert_eq!(
+                BigramTokenizer.tokenize("東京都 に"),
+                vec!["東京", "京都", "に"]
+            )
+        }
+    }
+
+    mod test_matches {
+        use super::*;
+
+        #[test]
+        fn test_matches_keywords() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("rust", doc));
+            assert!(matches("RUST search", doc));
+            assert!(!matches("rust golang", doc));
+            assert!(matches("golang OR rust", doc));
+            assert!(!matches("pars", doc));
+            assert!(matches("", doc));
+        }
+
+        #[test]
+        fn test_matches_phrase_keywords() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("\"search queries\"", doc));
+            assert!(!matches("\"queries search\"", doc));
+            assert!(matches("queries search", doc));
+        }
+
+        #[test]
+        fn test_matches_proximity() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("\"parsing queries\"~1", doc));
+            assert!(!matches("\"parsing rust\"~2", doc));
+            assert!(matches("\"parsing rust\"~3", doc));
+            assert!(!matches("\"queries search\"~5", doc));
+            assert!(matches("\"search queries\"~1", doc));
+        }
+
+        #[test]
+        fn test_matches_negative() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("rust -golang", doc));
+            assert!(!matches("rust -\"in rust\"", doc));
+            assert!(matches("-(golang OR java)", doc));
+        }
+
+        #[test]
+        fn test_matches_boolean() {
+            let doc = "Parsing search queries in Rust";
+            let parser = Parser::new().with_default_operator(DefaultOperator::Or);
+            let matches = |query: &str| parser.parse(query).unwrap().matches(doc);
+            assert!(matches("rust golang"));
+            assert!(!matches("java golang"));
+            assert!(matches("+rust golang"));
+            assert!(!matches("+golang rust"));
+            assert!(!matches("rust golang -search"));
+            assert!(matches("-golang"));
+            assert!(matches("(java -rust) search"));
+        }
+
+        #[test]
+        fn test_matches_prefix_and_wildcard() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("pars*", doc));
+            assert!(matches("RU*", doc));
+            assert!(!matches("arsing*", doc));
+            assert!(matches("qu?ries", doc));
+            assert!(matches("*ear*", doc));
+            assert!(!matches("qu?ry", doc));
+            assert!(!matches("pars\\*", doc));
+            assert!(matches("title:rust* OR -title:*", doc));
+        }
+
+        #[test]
+        fn test_matches_regex() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("/que.*s/", doc));
+            assert!(matches("/RUST|go/", doc));
+            assert!(!matches("/ues/", doc));
+            assert!(!matches("-/r[a-z]st/", doc));
+            assert!(matches("title:/rust/ OR /s.{4}h/", doc));
+        }
+
+        #[test]
+        fn test_matches_near() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("parsing NEAR/2 in", doc));
+            assert!(!matches("parsing NEAR/1 in", doc));
+            assert!(matches("rust NEAR/3 parsing", doc));
+            assert!(!matches("rust ONEAR/3 parsing", doc));
+            assert!(matches("parsing ONEAR/3 rust", doc));
+            assert!(matches("\"search queries\" NEAR/1 rust", doc));
+            assert!(matches("(golang OR search) NEAR/0 que*", doc));
+            assert!(!matches("parsing NEAR/5 golang", doc));
+            assert!(matches("title:(rust NEAR/1 language)", &article()));
+            assert!(matches("\"parsing queries\"~1 NEAR/1 rust", doc));
+            assert!(!matches("\"parsing queries\"~1 NEAR/0 rust", doc));
+        }
+
+        #[test]
+        fn test_matches_near_in_long_text() {
+            // the positions are merged, so the time grows linearly with the number of the tokens
+            let doc = format!("{} a x b y c", "a b c x y z ".repeat(2000));
+            let start = std::time::Instant::now();
+            assert!(matches("a NEAR/5 b NEAR/5 c NEAR/5 a", &doc));
+            assert!(matches("a ONEAR/1 b ONEAR/1 c", &doc));
+            assert!(!matches("a NEAR/5 b NEAR/5 c NEAR/5 golang", &doc));
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        }
+
+        #[test]
+        fn test_matches_near_without_positions() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("parsing NEAR/3 -golang", doc));
+            assert!(!matches("parsing NEAR/3 -rust", doc));
+            assert!(matches("rust NEAR/0 (parsing AND queries)", doc));
+        }
+
+        #[test]
+        fn test_matches_near_with_regex() {
+            let doc = "Rust is a systems programming language";
+            assert!(matches("/ru.t/ NEAR/4 lang*", doc));
+            assert!(!matches("/ru.t/ NEAR/3 lang*", doc));
+            assert!(matches("/pro.*/ ONEAR/0 /lang.*/", doc));
+            assert!(!matches("/lang.*/ ONEAR/0 /pro.*/", doc));
+        }
+
+        #[test]
+        fn test_matches_boosted() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("rust^2", doc));
+            assert!(matches("\"search queries\"^0.5 (golang OR parsing)^3", doc));
+            assert!(!matches("golang^2", doc));
+            assert!(!matches("-rust^2", doc));
+            assert!(matches("parsing NEAR/1 search^2", doc));
+        }
+
+        #[test]
+        fn test_matches_fuzzy() {
+            let doc = "Parsing search queries in Rust";
+            assert!(matches("rust~", doc));
+            assert!(matches("rsut~", doc));
+            assert!(matches("rast~1", doc));
+            assert!(!matches("rsut~1", doc));
+            assert!(matches("serch~1", doc));
+            assert!(!matches("golang~", doc));
+            assert!(matches("title:rust~ OR rust~0", doc));
+        }
+
+        #[test]
+        fn test_edit_distance() {
+            assert_eq!(edit_distance("rust", "rust"), 0);
+            assert_eq!(edit_distance("rust", "rest"), 1);
+            assert_eq!(edit_distance("rust", "rsut"), 2);
+            assert_eq!(edit_distance("", "go"), 2);
+            assert_eq!(edit_distance("東京", "京都"), 2);
+        }
+
+        #[test]
+        fn test_matches_target() {
+            let doc = article();
+            assert!(matches("title:rust", &doc));
+            assert!(!matches("author:rust", &doc));
+            assert!(!matches("publisher:rust", &doc));
+            assert!(matches("-publisher:rust", &doc));
+            assert!(matches("author:\"steve klabnik\" klabnik", &doc));
+            assert!(matches(
+                "title^2:(golang OR \"programming language\")",
+                &doc
+            ));
+        }
+
+        #[test]
+        fn test_matches_range() {
+            let doc = HashMap::from([
+                ("price".to_string(), "100"),
+                ("date".to_string(), "2020-01-01"),
+            ]);
+            assert!(matches("price:[10 TO 100]", &doc));
+            assert!(!matches("price:[10 TO 100}", &doc));
+            assert!(matches("price:{99.5 TO *}", &doc));
+            assert!(!matches("price:[* TO 20]", &doc));
+            assert!(matches("date:[2019-12-31 TO 2020-01-01]", &doc));
+            assert!(!matches("date:{2020-01-01 TO *}", &doc));
+            assert!(!matches("size:[* TO *]", &doc));
+        }
+
+        #[test]
+        fn test_matches_comparison() {
+            let doc = HashMap::from([("price".to_string(), "100"), ("stars".to_string(), "4.5")]);
+            assert!(matches("price:>=100", &doc));
+            assert!(!matches("price:>100", &doc));
+            assert!(matches("price:>99.9", &doc));
+            assert!(matches("stars:<5", &doc));
+            assert!(!matches("stars:<=4", &doc));
+            assert!(!matches("size:>0", &doc));
+        }
+
+        #[test]
+        fn test_matches_exists() {
+            let doc = HashMap::from([("title".to_string(), "Rust"), ("note".to_string(), " ")]);
+            assert!(matches("_exists_:title", &doc));
+            assert!(!matches("_exists_:note", &doc));
+            assert!(!matches("_exists_:body", &doc));
+            assert!(matches("rust -_exists_:body", &doc));
+            assert!(!matches("_exists_:title", "Rust"));
+        }
+
+        #[test]
+        fn test_matches_with_bigram_tokenizer() {
+            let doc = "東京都に住んでいます";
+            let condition = parse_query_to_condition("東京 -大阪").unwrap();
+            assert!(condition.matches_with(doc, &BigramTokenizer));
+            assert!(!condition.matches(doc));
+        }
+    }
+
+    mod test_matcher {
+        use super::*;
+        use std::cell::Cell;
+
+        #[test]
+        fn test_matcher_is_same_as_matches() {
+            let docs = [
+                "The Rust Programming Language",
+                "Programming in Go",
+                "rusty regex 2024",
+            ];
+            [
+                "rust -go",
+                "title:rust OR programming",
+                "rust* /reg.x/ ?o",
+                "\"rust programming\"~1 OR langauge~2",
+                "rust NEAR/2 language",
+                "+rust^2 programming -go",
+            ]
+            .into_iter()
+            .for_each(|query| {
+                let condition = parse_query_to_condition(query).unwrap();
+                let matcher = condition.matcher();
+                docs.iter().for_each(|doc| {
+                    assert_eq!(
+                        matcher.matches(*doc),
+                        condition.matches(*doc),
+                        "{} {}",
+                        query,
+                        doc
+                    )
+                })
+            })
+        }
+
+        #[test]
+        fn test_matcher_tokenizes_terms_once() {
+            struct CountingTokenizer(Cell<usize>);
+            impl TextTokenizer for CountingTokenizer {
+                fn tokenize(&self, text: &str) -> Vec<String> {
+                    self.0.set(self.0.get() + 1);
+                    SimpleTokenizer.tokenize(text)
+                }
+            }
+            let tokenizer = CountingTokenizer(Cell::new(0));
+            let matcher = parse_query_to_condition("rust \"programming language\" -go")
+                .unwrap()
+                .matcher_with(&tokenizer);
+            assert_eq!(tokenizer.0.get(), 3);
+            // only the text of the document is tokenized for each condition
+            assert!(matcher.matches("The Rust Programming Language"));
+            assert_eq!(tokenizer.0.get(), 6);
+        }
+    }
+}
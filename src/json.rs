@@ -0,0 +1,429 @@
+use crate::{Boost, Comparison, Condition, Occur, Operator, Versioned};
+use eyre::{eyre, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// increased when the json representation of the conditions is changed
+pub const JSON_VERSION: u32 = 1;
+
+// the json representation of `Condition`, the tuple variants are written with named fields
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ConditionJson {
+    None,
+    Keyword {
+        value: String,
+    },
+    PhraseKeyword {
+        value: String,
+    },
+    Prefix {
+        value: String,
+    },
+    Wildcard {
+        value: String,
+    },
+    Fuzzy {
+        term: String,
+        max_edits: u8,
+    },
+    Proximity {
+        phrase: String,
+        slop: u32,
+    },
+    Regex {
+        value: String,
+    },
+    Range {
+        target: String,
+        lower: Option<String>,
+        upper: Option<String>,
+        inclusive_lower: bool,
+        inclusive_upper: bool,
+    },
+    Comparison {
+        target: String,
+        comparison: Comparison,
+        value: String,
+    },
+    Exists {
+        target: String,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+    Operator {
+        operator: Operator,
+        conditions: Vec<Condition>,
+    },
+    Targeted {
+        target: String,
+        condition: Box<Condition>,
+    },
+    Boolean {
+        clauses: Vec<ClauseJson>,
+    },
+    Boosted {
+        condition: Box<Condition>,
+        boost: Boost,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ClauseJson {
+    occur: Occur,
+    condition: Condition,
+}
+
+impl From<Condition> for ConditionJson {
+    fn from(condition: Condition) -> Self {
+        match condition {
+            Condition::None => ConditionJson::None,
+            Condition::Keyword(value) => ConditionJson::Keyword { value },
+            Condition::PhraseKeyword(value) => ConditionJson::PhraseKeyword { value },
+            Condition::Prefix(value) => ConditionJson::Prefix { value },
+            Condition::Wildcard(value) => ConditionJson::Wildcard { value },
+            Condition::Fuzzy { term, max_edits } => ConditionJson::Fuzzy { term, max_edits },
+            Condition::Proximity { phrase, slop } => ConditionJson::Proximity { phrase, slop },
+            Condition::Regex(value) => ConditionJson::Regex { value },
+            Condition::Range {
+                target,
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+            } => ConditionJson::Range {
+                target,
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+            },
+            Condition::Comparison {
+                target,
+                comparison,
+                value,
+            } => ConditionJson::Comparison {
+                target,
+                comparison,
+                value,
+            },
+            Condition::Exists { target } => ConditionJson::Exists { target },
+            Condition::Not(condition) => ConditionJson::Not { condition },
+            Condition::Operator(operator, conditions) => ConditionJson::Operator {
+                operator,
+                conditions,
+            },
+            Condition::Targeted(target, condition) => ConditionJson::Targeted { target, condition },
+            Condition::Boolean(clauses) => ConditionJson::Boolean {
+                clauses: clauses
+                    .into_iter()
+                    .map(|(occur, condition)| ClauseJson { occur, condition })
+                    .collect(),
+            },
+            Condition::Boosted { condition, boost } => ConditionJson::Boosted { condition, boost },
+        }
+    }
+}
+
+impl From<ConditionJson> for Condition {
+    fn from(json: ConditionJson) -> Self {
+        match json {
+            ConditionJson::None => Condition::None,
+            ConditionJson::Keyword { value } => Condition::Keyword(value),
+            ConditionJson::PhraseKeyword { value } => Condition::PhraseKeyword(value),
+            ConditionJson::Prefix { value } => Condition::Prefix(value),
+            ConditionJson::Wildcard { value } => Condition::Wildcard(value),
+            ConditionJson::Fuzzy { term, max_edits } => Condition::Fuzzy { term, max_edits },
+            ConditionJson::Proximity { phrase, slop } => Condition::Proximity { phrase, slop },
+            ConditionJson::Regex { value } => Condition::Regex(value),
+            ConditionJson::Range {
+                target,
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+            } => Condition::Range {
+                target,
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+            },
+            ConditionJson::Comparison {
+                target,
+                comparison,
+                value,
+            } => Condition::Comparison {
+                target,
+                comparison,
+                value,
+            },
+            ConditionJson::Exists { target } => Condition::Exists { target },
+            ConditionJson::Not { condition } => Condition::Not(condition),
+            ConditionJson::Operator {
+                operator,
+                conditions,
+            } => Condition::Operator(operator, conditions),
+            ConditionJson::Targeted { target, condition } => Condition::Targeted(target, condition),
+            ConditionJson::Boolean { clauses } => Condition::Boolean(
+                clauses
+                    .into_iter()
+                    .map(|clause| (clause.occur, clause.condition))
+                    .collect(),
+            ),
+            ConditionJson::Boosted { condition, boost } => Condition::Boosted { condition, boost },
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Versioned<T> {
+    pub fn new(condition: T) -> Self {
+        Self {
+            version: JSON_VERSION,
+            condition,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    // the json of the other versions is rejected, not to read it wrongly
+    pub fn from_json(json: &str) -> Result<Self> {
+        let versioned = serde_json::from_str::<Self>(json)?;
+        match versioned.version {
+            JSON_VERSION => Ok(versioned),
+            version => Err(eyre!("unsupported json version: {}", version)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse_query_to_condition, parse_query_to_condition_on_target, ConditionOnTarget,
+        DefaultOperator, Parser,
+    };
+    use serde_json::json;
+
+    mod test_json {
+        use super::*;
+
+        #[test]
+        fn test_condition_json() {
+            let condition =
+                parse_query_to_condition("Ａ OR -title:\"Ｂ Ｃ\" OR body:(Ｄ Ｅ)").unwrap();
+            assert_eq!(
+                serde_json::to_value(&condition).unwrap(),
+                json!({
+                    "type": "operator",
+                    "operator": "or",
+                    "conditions": [
+                        { "type": "keyword", "value": "Ａ" },
+                        {
+                            "type": "not",
+                            "condition": {
+                                "type": "targeted",
+                                "target": "title",
+                                "condition": { "type": "phrase_keyword", "value": "Ｂ Ｃ" }
+                            }
+                        },
+                        {
+                            "type": "targeted",
+                            "target": "body",
+                            "condition": {
+                                "type": "operator",
+                                "operator": "and",
+                                "conditions": [
+                                    { "type": "keyword", "value": "Ｄ" },
+                                    { "type": "keyword", "value": "Ｅ" }
+                                ]
+                            }
+                        }
+                    ]
+                })
+            )
+        }
+
+        #[test]
+        fn test_condition_on_target_json() {
+            let condition = parse_query_to_condition_on_target("title^0.5:Ａ").unwrap();
+            assert_eq!(
+                serde_json::to_value(&condition).unwrap(),
+                json!({
+                    "type": "keyword",
+                    "condition": "Ａ",
+                    "target": { "name": "title", "weight": 0.5 }
+                })
+            )
+        }
+
+        #[test]
+        fn test_fuzzy_json() {
+            let condition = parse_query_to_condition("rust~1").unwrap();
+            let value = serde_json::to_value(&condition).unwrap();
+            assert_eq!(
+                value,
+                json!({ "type": "fuzzy", "term": "rust", "max_edits": 1 })
+            );
+            assert_eq!(
+                serde_json::from_value::<Condition>(value).unwrap(),
+                condition
+            )
+        }
+
+        #[test]
+        fn test_near_json() {
+            let condition = parse_query_to_condition("Ａ NEAR/5 Ｂ ONEAR/3 Ｃ").unwrap();
+            let value = serde_json::to_value(&condition).unwrap();
+            assert_eq!(
+                value,
+                json!({
+                    "type": "operator",
+                    "operator": { "ordered_near": 3 },
+                    "conditions": [
+                        {
+                            "type": "operator",
+                            "operator": { "near": 5 },
+                            "conditions": [
+                                { "type": "keyword", "value": "Ａ" },
+                                { "type": "keyword", "value": "Ｂ" }
+                            ]
+                        },
+                        { "type": "keyword", "value": "Ｃ" }
+                    ]
+                })
+            );
+            assert_eq!(
+                serde_json::from_value::<Condition>(value).unwrap(),
+                condition
+            )
+        }
+
+        #[test]
+        fn test_boosted_json() {
+            let condition = parse_query_to_condition("rust^2 (Ａ OR Ｂ)^0.5").unwrap();
+            let value = serde_json::to_value(&condition).unwrap();
+            assert_eq!(
+                value,
+                json!({
+                    "type": "operator",
+                    "operator": "and",
+                    "conditions": [
+                        {
+                            "type": "boosted",
+                            "condition": { "type": "keyword", "value": "rust" },
+                            "boost": 2.0
+                        },
+                        {
+                            "type": "boosted",
+                            "condition": {
+                                "type": "operator",
+                                "operator": "or",
+                                "conditions": [
+                                    { "type": "keyword", "value": "Ａ" },
+                                    { "type": "keyword", "value": "Ｂ" }
+                                ]
+                            },
+                            "boost": 0.5
+                        }
+                    ]
+                })
+            );
+            assert_eq!(
+                serde_json::from_value::<Condition>(value).unwrap(),
+                condition
+            );
+            assert_eq!(
+                serde_json::to_value(condition.identify_target()).unwrap()["conditions"][1]
+                    ["boost"],
+                json!(0.5)
+            );
+            // the boost which overflows `f32` is not finite
+            assert!(serde_json::from_value::<Condition>(json!({
+                "type": "boosted",
+                "condition": { "type": "keyword", "value": "rust" },
+                "boost": 1e39
+            }))
+            .is_err())
+        }
+
+        #[test]
+        fn test_boolean_json() {
+            let condition = Parser::new()
+                .with_default_operator(DefaultOperator::Or)
+                .parse("+Ａ Ｂ -Ｃ")
+                .unwrap();
+            let value = serde_json::to_value(&condition).unwrap();
+            assert_eq!(
+                value,
+                json!({
+                    "type": "boolean",
+                    "clauses": [
+                        { "occur": "must", "condition": { "type": "keyword", "value": "Ａ" } },
+                        { "occur": "should", "condition": { "type": "keyword", "value": "Ｂ" } },
+                        { "occur": "must_not", "condition": { "type": "keyword", "value": "Ｃ" } }
+                    ]
+                })
+            );
+            assert_eq!(
+                serde_json::from_value::<Condition>(value).unwrap(),
+                condition
+            )
+        }
+    }
+
+    mod test_versioned {
+        use super::*;
+
+        #[test]
+        fn test_versioned_condition_round_trip() {
+            [
+                "",
+                "Ａ１ Ａ２ OR -\"Ｐ １\"",
+                "-(Ａ OR title:(Ｂ -Ｃ)) body^2:\"Ｄ\"",
+                "rust* -title:te?t",
+                "/err(or)?\\d+/ -log:/a b/",
+                "_exists_:title -_exists_:body",
+            ]
+            .iter()
+            .for_each(|query| {
+                let condition = parse_query_to_condition(query).unwrap();
+                let json = Versioned::new(condition.clone()).to_json().unwrap();
+                assert_eq!(
+                    Versioned::<Condition>::from_json(&json).unwrap().condition,
+                    condition,
+                    "{}",
+                    json
+                )
+            })
+        }
+
+        #[test]
+        fn test_versioned_condition_on_target_round_trip() {
+            let condition =
+                parse_query_to_condition_on_target("-(Ａ OR title^0.2:(Ｂ -Ｃ)) body^2:\"Ｄ\"")
+                    .unwrap();
+            let json = Versioned::new(condition.clone()).to_json().unwrap();
+            assert_eq!(
+                Versioned::<ConditionOnTarget>::from_json(&json)
+                    .unwrap()
+                    .condition,
+                condition
+            )
+        }
+
+        #[test]
+        fn test_versioned_unsupported_version() {
+            let actual = Versioned::<Condition>::from_json(
+                r#"{ "version": 2, "condition": { "type": "none" } }"#,
+            );
+            assert_eq!(
+                actual.unwrap_err().to_string(),
+                "unsupported json version: 2"
+            )
+        }
+    }
+}
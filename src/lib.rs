@@ -1,25 +1,137 @@
 mod bnf_approach;
+mod boost;
+pub mod elasticsearch;
+mod escape;
+pub use escape::escape_term;
+pub mod evaluator;
+mod json;
+mod near;
+mod occur;
+mod parser;
+pub use json::JSON_VERSION;
+mod query_string;
 mod regex_approach;
+mod syntax_error;
+mod term;
+mod typed_value;
 
-use crate::regex_approach::layered_query::LayeredQueries;
-use crate::regex_approach::query::Query;
 use eyre::Result;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub fn parse_query_to_condition(query: &str) -> Result<Condition> {
-    LayeredQueries::parse(Query::new(query.into()))?.to_condition()
+    parse_query_to_condition_with(query, Approach::Regex)
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub fn parse_query_to_condition_on_target(query: &str) -> Result<ConditionOnTarget> {
+    parse_query_to_condition_on_target_with(query, Approach::Regex)
+}
+
+pub fn parse_query_to_condition_with(query: &str, approach: Approach) -> Result<Condition> {
+    Parser::new().with_approach(approach).parse(query)
+}
+
+pub fn parse_query_to_condition_on_target_with(
+    query: &str, approach: Approach,
+) -> Result<ConditionOnTarget> {
+    Parser::new().with_approach(approach).parse_on_target(query)
+}
+
+// spans are only available with the bnf approach, the regex approach rewrites the query with placeholders
+pub fn parse_query_to_spanned_condition(query: &str) -> Result<SpannedCondition> {
+    Parser::new().parse_with_span(query)
+}
+
+// the condition is parsed by the bnf approach, and what is corrected in the query is reported as warnings
+pub fn parse_query_to_condition_with_warnings(query: &str) -> Result<ParsedQuery> {
+    Parser::new().parse_with_warnings(query)
+}
+
+// the queries which would be corrected are rejected with the leftmost error in them,
+// the correct queries are parsed by the bnf approach
+pub fn parse_query_strict(query: &str) -> std::result::Result<Condition, SyntaxError> {
+    Parser::new().parse_strict(query)
+}
+
+// cheap to create and can be shared between threads, the regexes are compiled only once for all the parsers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parser {
+    approach: Approach,
+    symbolic_operators: bool,
+    default_operator: DefaultOperator,
+    exists_alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum Approach {
+    #[default]
+    Regex,
+    Bnf,
+}
+
+// how the conditions joined by spaces are matched, `NEAR/n` can't be the default
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum DefaultOperator {
+    #[default]
+    And,
+    Or,
+}
+
+// serialized as `{"type": "keyword", "value": "rust"}`, see `json.rs` for all the variants
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(into = "json::ConditionJson", from = "json::ConditionJson")]
 pub enum Condition {
     None,
     Keyword(String),
     PhraseKeyword(String),
+    // without the last `*`
+    Prefix(String),
+    // with `*` and `?`, and `\` to escape them
+    Wildcard(String),
+    // `rust~1`, the max edits is from 0 to 2, and 2 when it's omitted
+    Fuzzy {
+        term: String,
+        max_edits: u8,
+    },
+    // `"quick fox"~5`, the words of the phrase in order with at most `slop` words between them
+    Proximity {
+        phrase: String,
+        slop: u32,
+    },
+    // `price:[10 TO 100]` and `date:{2020-01-01 TO *}`, the open end `*` is None
+    Range {
+        target: String,
+        lower: Option<String>,
+        upper: Option<String>,
+        inclusive_lower: bool,
+        inclusive_upper: bool,
+    },
+    // `price:>=100` and `stars:<3`
+    Comparison {
+        target: String,
+        comparison: Comparison,
+        value: String,
+    },
+    // `_exists_:title`, the documents which have the field
+    Exists {
+        target: String,
+    },
     Not(Box<Condition>),
     Operator(Operator, Vec<Condition>),
+    Targeted(String, Box<Condition>),
+    // `/err(or)?\d+/`, the pattern is validated by the `regex` crate and matches a whole token
+    Regex(String),
+    // `+A B -C` with `Operator::Or` as the default operator, `+A` alone is kept until the default operator is applied
+    Boolean(Vec<(Occur, Condition)>),
+    // `rust^2` and `(a OR b)^0.5`
+    Boosted {
+        condition: Box<Condition>,
+        boost: Boost,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum ConditionOnTarget {
     None,
     Keyword {
@@ -30,6 +142,43 @@ pub enum ConditionOnTarget {
         condition: String,
         target: Option<Target>,
     },
+    Prefix {
+        condition: String,
+        target: Option<Target>,
+    },
+    Wildcard {
+        condition: String,
+        target: Option<Target>,
+    },
+    Fuzzy {
+        condition: String,
+        max_edits: u8,
+        target: Option<Target>,
+    },
+    Regex {
+        condition: String,
+        target: Option<Target>,
+    },
+    Proximity {
+        condition: String,
+        slop: u32,
+        target: Option<Target>,
+    },
+    Range {
+        lower: Option<String>,
+        upper: Option<String>,
+        inclusive_lower: bool,
+        inclusive_upper: bool,
+        target: Option<Target>,
+    },
+    Comparison {
+        comparison: Comparison,
+        value: String,
+        target: Option<Target>,
+    },
+    Exists {
+        target: Option<Target>,
+    },
     Not {
         condition: Box<ConditionOnTarget>,
         target: Option<Target>,
@@ -39,18 +188,154 @@ pub enum ConditionOnTarget {
         conditions: Vec<ConditionOnTarget>,
         target: Option<Target>,
     },
+    Boolean {
+        clauses: Vec<(Occur, ConditionOnTarget)>,
+        target: Option<Target>,
+    },
+    Boosted {
+        condition: Box<ConditionOnTarget>,
+        boost: Boost,
+        target: Option<Target>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+// the weight of `^2`, which is always a finite number, serialized as the number
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
+pub struct Boost(f32);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Target {
     name: String,
     weight: Option<f32>,
 }
 
+impl Target {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+}
+
+// byte offsets in the original query, `query[span.start..span.end]`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// the condition is built from the nodes by `SpannedCondition::condition`, not to keep a copy of the subtree in each node
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct SpannedCondition {
+    pub node: ConditionNode,
+    pub span: Span,
+    pub children: Vec<SpannedCondition>,
+}
+
+// a condition without its children, e.g. `Not` of `-A` and `Operator(And)` of `A B`
 #[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub enum ConditionNode {
+    // the conditions without children like `Keyword` and `Range`
+    Leaf(Condition),
+    Not,
+    Targeted(String),
+    Boosted(Boost),
+    // the occurs of the clauses in the same order as the children
+    Boolean(Vec<Occur>),
+    Operator(Operator),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub enum SyntaxError {
+    UnbalancedBracket(Span),
+    UnterminatedQuote(Span),
+    DanglingOperator(Span),
+    EmptyGroup(Span),
+    EmptyPhrase(Span),
+    InvalidRegex(Span),
+    // `rust~5`, the max edits larger than 2 is corrected to 2
+    TooManyEdits(Span),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ParsedQuery {
+    pub condition: Condition,
+    pub warnings: Vec<Warning>,
+}
+
+// `text` is the part of the query where the error is
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Warning {
+    pub error: SyntaxError,
+    pub text: String,
+}
+
+// the json with the version of its representation, to cache or send the conditions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub condition: T,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Operator {
     And,
     Or,
+    // `A NEAR/5 B`, the terms with at most 5 tokens between them in any order
+    Near(u32),
+    // `A ONEAR/5 B`, the same as `Near` but the terms are in order
+    OrderedNear(u32),
+}
+
+// how a clause of `Condition::Boolean` is matched, the should clauses are optional when there is a must clause
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Occur {
+    Must,
+    Should,
+    MustNot,
+}
+
+// `>`, `>=`, `<` and `<=`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Text,
+    Integer,
+    Float,
+    // `2020-01-01` and `2020-01-01T12:00:00Z`, kept as the text
+    Date,
+    Bool,
+}
+
+// serialized as `{"type": "integer", "value": 2020}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum TypedValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Date(String),
+    Bool(bool),
+}
+
+// the value types of the fields, the types of the other fields are inferred from the values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    fields: HashMap<String, ValueType>,
 }
 
 #[cfg(test)]
@@ -407,4 +692,1529 @@ mod tests {
             )
         }
     }
+
+    mod query_on_target {
+        use super::*;
+
+        fn target(name: &str) -> Option<Target> {
+            Some(Target {
+                name: name.into(),
+                weight: None,
+            })
+        }
+
+        #[test]
+        fn test_keyword_on_target() {
+            let actual = parse_query_to_condition_on_target("title:rust").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Keyword {
+                    condition: "rust".into(),
+                    target: target("title"),
+                }
+            )
+        }
+
+        #[test]
+        fn test_phrase_keyword_on_target() {
+            let actual = parse_query_to_condition_on_target("title:\"exact phrase\"").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::PhraseKeyword {
+                    condition: "exact phrase".into(),
+                    target: target("title"),
+                }
+            )
+        }
+
+        #[test]
+        fn test_minus_on_target() {
+            let actual =
+                parse_query_to_condition_on_target("-author:bob -author:\"bob smith\"").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Not {
+                            condition: Box::new(ConditionOnTarget::Keyword {
+                                condition: "bob".into(),
+                                target: target("author"),
+                            }),
+                            target: target("author"),
+                        },
+                        ConditionOnTarget::Not {
+                            condition: Box::new(ConditionOnTarget::PhraseKeyword {
+                                condition: "bob smith".into(),
+                                target: target("author"),
+                            }),
+                            target: target("author"),
+                        },
+                    ],
+                    target: None,
+                }
+            )
+        }
+
+        #[test]
+        fn test_negative_brackets_on_target() {
+            let actual =
+                parse_query_to_condition_on_target("-title:(rust OR \"go lang\") java").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Not {
+                            condition: Box::new(ConditionOnTarget::Operator {
+                                operator: Operator::Or,
+                                conditions: vec![
+                                    ConditionOnTarget::Keyword {
+                                        condition: "rust".into(),
+                                        target: target("title"),
+                                    },
+                                    ConditionOnTarget::PhraseKeyword {
+                                        condition: "go lang".into(),
+                                        target: target("title"),
+                                    },
+                                ],
+                                target: target("title"),
+                            }),
+                            target: target("title"),
+                        },
+                        ConditionOnTarget::Keyword {
+                            condition: "java".into(),
+                            target: None,
+                        },
+                    ],
+                    target: None,
+                }
+            )
+        }
+
+        #[test]
+        fn test_brackets_on_target() {
+            let actual = parse_query_to_condition_on_target("title:(a OR b) AND body:c").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Operator {
+                            operator: Operator::Or,
+                            conditions: vec![
+                                ConditionOnTarget::Keyword {
+                                    condition: "a".into(),
+                                    target: target("title"),
+                                },
+                                ConditionOnTarget::Keyword {
+                                    condition: "b".into(),
+                                    target: target("title"),
+                                },
+                            ],
+                            target: target("title"),
+                        },
+                        ConditionOnTarget::Keyword {
+                            condition: "c".into(),
+                            target: target("body"),
+                        },
+                    ],
+                    target: None,
+                }
+            )
+        }
+
+        #[test]
+        fn test_nested_brackets_on_target() {
+            let actual =
+                parse_query_to_condition_on_target("title:(a (\"b c\" OR author:d))").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Keyword {
+                            condition: "a".into(),
+                            target: target("title"),
+                        },
+                        ConditionOnTarget::Operator {
+                            operator: Operator::Or,
+                            conditions: vec![
+                                ConditionOnTarget::PhraseKeyword {
+                                    condition: "b c".into(),
+                                    target: target("title"),
+                                },
+                                ConditionOnTarget::Keyword {
+                                    condition: "d".into(),
+                                    target: target("author"),
+                                },
+                            ],
+                            target: target("title"),
+                        },
+                    ],
+                    target: target("title"),
+                }
+            )
+        }
+    }
+
+    mod approach {
+        use super::*;
+
+        #[test]
+        fn test_bnf_approach_is_same_as_regex_approach() {
+            vec![
+                "",
+                "word1 word2",
+                "word1 OR word2 AND word3",
+                "word1 AND (word2 OR word3)",
+                "\"word1 AND (word2 OR word3)\" word4",
+                "-word1 -\"word2\" -(word3 OR word4)",
+                "(word１ and -word２) or ((\"phrase word １\" or -\"phrase word ２\") and -(\" a long phrase word \" or word３))",
+                "A AND () AND B",
+                "A OR B) AND (C OR D",
+                "(A OR B) AND (C",
+                "(((A OR B)) AND C",
+                "A AND (\"\" OR \"\") AND B",
+                "A AND \"\" AND B",
+                "\"A\" OR \"B OR C",
+                "A AND OR B",
+                "(A OR (B OR C)) AND D",
+                "A(B OR C)D",
+                "A\"B\"C",
+                "　ＡＡＡ　（”１１１　ＣＣＣ”　（-（　ＤＤＤ　エエエ　）　ＦＦＦ）　ＧＧＧ　（ＨＨＨ　-”あああ　いいい”　ううう））",
+                "title:rust title:\"exact phrase\" -author:bob title:(a OR b)",
+                "rust* -te?t title:go* (5\\* OR *)",
+                "rust~ -title:go～1 (rust\\~1 OR ~)",
+                "\"quick fox\"~5 -title:\"a b\"～1 (\"c d\"~ OR -\"e\"~2x)",
+                "(price:[1 TO 2] OR x) title:(a:{* TO 5]) -:[1 TO 2] a:b:[1 TO 2] price:[1 TO",
+                "price:>=100 OR -stars:＜3 OR (date:<=2020-01-01 price:> >3)",
+                "title:(rust OR go) -title:java -body:(\"a b\" c) OR -:(d) --e:(f)",
+                "a && b ||| ｜ AT&T ＆＆ f || (c & -d) |(e)",
+                "NOT java a AND NOT (x OR y) OR !b !\"c d\" ＮＯＴ title:(e) ! NOTE (f NOT)",
+                "+a b -c +(d OR e) +\"f g\" +title:(h) OR +title:\"i\" + ++j",
+                "\\(a\\) \\\"b \\-c d\\:e ti\\ tle:(f) \\OR \"g \\\" h\"~2 i\\ j* \\",
+                "/err(or)?\\d+/ -title:/a (b)/ a/b/ \\/c/ \"/d/\" title:(/e\\/f/ OR g) !/(/ //",
+                "_exists_:a -_exists_:b\\:c NOT _exists_:d title:(_exists_:e OR f) _exists_: _exists_:g:h",
+                "a b NEAR/3 c OR d ONEAR/2 \"e f\" ＮＥＡＲ／２ g NEAR/1 OR k NEAR/2",
+                "(h) NEAR/1 (i j) NEAR/2 x y (z) ONEAR/3 NEAR/1",
+                "(a OR b) NEAR/3 c NEAR/3 AND d -x NEAR/5 title:(e NEAR/1 f*) NEAR/4 -(g NEAR/2 h) NEAR",
+                "rust^2 \"a b\"~2^3 -title:(x y)^0.5 +(c OR d)^2 e^NaN ru*^2 title:\"f\"^2 g^2a",
+                "(a -b)^2 NEAR/1 c^3 \"d\"^2e (f)^x h\\^2 /i^j/^2 (title:k)^1.5 ^2",
+            ]
+            .into_iter()
+            .for_each(|query| {
+                assert_eq!(
+                    parse_query_to_condition_with(query, Approach::Bnf).unwrap(),
+                    parse_query_to_condition_with(query, Approach::Regex).unwrap(),
+                    "{}",
+                    query
+                )
+            })
+        }
+
+        #[test]
+        fn test_bnf_approach_is_same_as_regex_approach_with_or_operator() {
+            vec![
+                "+a b -c",
+                "x (+a b) OR (c -d) y",
+                "-(a -b) c title:(d -e) ((f -g))",
+                "+title:(a b) +\"c d\" -title:\"e\" f",
+                "+a^2 (b -c)^3 -\"d\"^0.5 +(e)^2",
+                "a AND (b c) d AND -e OR f NEAR/2 g AND h",
+            ]
+            .into_iter()
+            .for_each(|query| {
+                let parser = Parser::new().with_default_operator(DefaultOperator::Or);
+                assert_eq!(
+                    parser
+                        .clone()
+                        .with_approach(Approach::Bnf)
+                        .parse(query)
+                        .unwrap(),
+                    parser.with_approach(Approach::Regex).parse(query).unwrap(),
+                    "{}",
+                    query
+                )
+            })
+        }
+
+        #[test]
+        fn test_and_has_higher_priority_across_brackets() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let actual = parse_query_to_condition_with("A OR B AND (C)", approach).unwrap();
+                    assert_eq!(
+                        actual,
+                        Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Keyword("A".into()),
+                                Condition::Operator(
+                                    Operator::And,
+                                    vec![
+                                        Condition::Keyword("B".into()),
+                                        Condition::Keyword("C".into()),
+                                    ]
+                                )
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_bnf_approach_is_same_as_regex_approach_with_brackets() {
+            [
+                "A OR B (C)",
+                "(A) OR B C",
+                "A (B OR C) D",
+                "(A OR B) AND C OR D",
+                "A AND (B) OR (C) D",
+                "-(A) OR B",
+                "title:(A) OR B C",
+                "A OR (B (C OR D) E) F",
+                "(A) NEAR/3 B C",
+                "A NEAR/3 (B) NEAR/5 C",
+                "NEAR/2 (A) B",
+                "(A) AND OR B",
+                "(A)^2 OR B C",
+            ]
+            .into_iter()
+            .for_each(|query| {
+                assert_eq!(
+                    parse_query_to_condition_with(query, Approach::Bnf).unwrap(),
+                    parse_query_to_condition_with(query, Approach::Regex).unwrap(),
+                    "{}",
+                    query
+                )
+            })
+        }
+    }
+
+    mod spanned_query {
+        use super::*;
+
+        #[test]
+        fn test_spanned_condition_is_same_as_bnf_approach() {
+            [
+                "Ａ１　-Ａ２ OR title:\"Ｐ１\"",
+                "-(Ａ１ OR (Ａ２)) AND \"\" ()",
+                "title:(Ａ１ OR -\"Ｐ１\") Ａ２)",
+            ]
+            .iter()
+            .for_each(|query| {
+                assert_eq!(
+                    parse_query_to_spanned_condition(query).unwrap().condition(),
+                    parse_query_to_condition_with(query, Approach::Bnf).unwrap(),
+                    "{}",
+                    query
+                )
+            })
+        }
+
+        #[test]
+        fn test_both_approaches_are_same_as_warnings() {
+            [
+                "+:\"p q\"",
+                "p:[1 TO 2]+OR~1t:\"x y\"",
+                "NOT NEAR/2",
+                "a NOT NEAR/2 b",
+                "++t:\"x\"",
+            ]
+            .iter()
+            .for_each(|query| {
+                let condition = parse_query_to_condition_with_warnings(query)
+                    .unwrap()
+                    .condition;
+                [Approach::Regex, Approach::Bnf]
+                    .into_iter()
+                    .for_each(|approach| {
+                        assert_eq!(
+                            parse_query_to_condition_with(query, approach).unwrap(),
+                            condition,
+                            "{} {:?}",
+                            query,
+                            approach
+                        )
+                    })
+            })
+        }
+
+        #[test]
+        fn test_spanned_condition_spans() {
+            let query = "rust -\"exact phrase\"";
+            let actual = parse_query_to_spanned_condition(query).unwrap();
+            assert_eq!(actual.span, Span { start: 0, end: 20 });
+            assert_eq!(actual.children[0].span, Span { start: 0, end: 4 });
+            assert_eq!(actual.children[1].span, Span { start: 5, end: 20 });
+            assert_eq!(
+                actual.children[1].children[0].span,
+                Span { start: 6, end: 20 }
+            );
+        }
+    }
+
+    mod strict_query {
+        use super::*;
+
+        #[test]
+        fn test_strict_correct_query() {
+            let query = "Ａ OR -(Ｂ title:\"Ｃ Ｄ\") AND Ｅ";
+            assert_eq!(
+                parse_query_strict(query).unwrap(),
+                parse_query_to_condition_with(query, Approach::Bnf).unwrap()
+            )
+        }
+
+        #[test]
+        fn test_strict_incorrect_queries() {
+            vec![
+                (
+                    "A OR B) AND (C",
+                    SyntaxError::UnbalancedBracket(Span { start: 6, end: 7 }),
+                ),
+                (
+                    "\"A\" OR \"B OR C",
+                    SyntaxError::UnterminatedQuote(Span { start: 7, end: 8 }),
+                ),
+                (
+                    "A AND OR B",
+                    SyntaxError::DanglingOperator(Span { start: 2, end: 5 }),
+                ),
+                (
+                    "A AND NOT",
+                    SyntaxError::DanglingOperator(Span { start: 6, end: 9 }),
+                ),
+                (
+                    "A AND () AND B",
+                    SyntaxError::DanglingOperator(Span { start: 2, end: 5 }),
+                ),
+                (
+                    "A () AND B",
+                    SyntaxError::EmptyGroup(Span { start: 2, end: 4 }),
+                ),
+                (
+                    "A \"\" AND B",
+                    SyntaxError::EmptyPhrase(Span { start: 2, end: 4 }),
+                ),
+                (
+                    "A -title:/[/ B",
+                    SyntaxError::InvalidRegex(Span { start: 3, end: 12 }),
+                ),
+                (
+                    "A title:rust~5^2 B",
+                    SyntaxError::TooManyEdits(Span { start: 2, end: 16 }),
+                ),
+            ]
+            .into_iter()
+            .for_each(|(query, error)| {
+                assert_eq!(parse_query_strict(query), Err(error), "{}", query)
+            })
+        }
+
+        #[test]
+        fn test_strict_first_error() {
+            let actual = parse_query_strict("A AND (B OR) C").unwrap_err();
+            assert_eq!(actual.to_string(), "operator without keyword at 9");
+            // the leftmost error is reported wherever it's found
+            let actual = parse_query_strict("A AND (B OR) (C").unwrap_err();
+            assert_eq!(actual.to_string(), "operator without keyword at 9");
+            let actual = parse_query_strict("A) AND (B OR C").unwrap_err();
+            assert_eq!(actual.to_string(), "unbalanced bracket at 1")
+        }
+    }
+
+    mod query_with_warnings {
+        use super::*;
+
+        #[test]
+        fn test_correct_query_without_warnings() {
+            let actual = parse_query_to_condition_with_warnings("A OR (B -\"C\")").unwrap();
+            assert_eq!(actual.warnings, vec![])
+        }
+
+        #[test]
+        fn test_incorrect_query_with_warnings() {
+            let query = "A OR B) AND \"\" AND OR (C";
+            let actual = parse_query_to_condition_with_warnings(query).unwrap();
+            assert_eq!(actual.condition, parse_query_to_condition(query).unwrap());
+            assert_eq!(
+                actual
+                    .warnings
+                    .iter()
+                    .map(|warning| warning.to_string())
+                    .collect::<Vec<String>>(),
+                vec![
+                    "removed unmatched bracket `)` at 6",
+                    "dropped dangling `AND` at 8",
+                    "ignored empty phrase `\"\"` at 12",
+                    "dropped dangling `AND` at 15",
+                    "removed unmatched bracket `(` at 22",
+                ]
+            )
+        }
+
+        #[test]
+        fn test_too_many_edits_with_warnings() {
+            let query = "rust~5 go~2 title:java~999^2";
+            let actual = parse_query_to_condition_with_warnings(query).unwrap();
+            assert_eq!(actual.condition, parse_query_to_condition(query).unwrap());
+            assert_eq!(
+                actual
+                    .warnings
+                    .iter()
+                    .map(|warning| warning.to_string())
+                    .collect::<Vec<String>>(),
+                vec![
+                    "reduced max edits of `rust~5` at 0",
+                    "reduced max edits of `title:java~999^2` at 12",
+                ]
+            )
+        }
+
+        #[test]
+        fn test_warnings_with_parser_settings() {
+            let parser = Parser::new()
+                .with_symbolic_operators(false)
+                .with_default_operator(DefaultOperator::Or)
+                .with_exists_alias("has");
+            let query = "a && b has:title OR";
+            let actual = parser.parse_with_warnings(query).unwrap();
+            assert_eq!(
+                actual.condition,
+                Condition::Boolean(vec![
+                    (Occur::Should, Condition::Keyword("a".into())),
+                    (Occur::Should, Condition::Keyword("&&".into())),
+                    (Occur::Should, Condition::Keyword("b".into())),
+                    (
+                        Occur::Should,
+                        Condition::Exists {
+                            target: "title".into()
+                        }
+                    ),
+                ])
+            );
+            assert_eq!(
+                actual.warnings,
+                vec![Warning {
+                    error: SyntaxError::DanglingOperator(Span { start: 17, end: 19 }),
+                    text: "OR".into(),
+                }]
+            );
+            assert_eq!(
+                parser.parse_strict(query),
+                Err(SyntaxError::DanglingOperator(Span { start: 17, end: 19 }))
+            );
+        }
+
+        #[test]
+        fn test_spanned_condition_with_parser_settings() {
+            [DefaultOperator::And, DefaultOperator::Or]
+                .into_iter()
+                .for_each(|default_operator| {
+                    let parser = Parser::new()
+                        .with_approach(Approach::Bnf)
+                        .with_default_operator(default_operator)
+                        .with_exists_alias("has");
+                    [
+                        "+a b -(c d) title:(+e f)^2 has:body^2",
+                        "a AND b c NEAR/2 d",
+                        "(a) OR b -c",
+                        "x (+a b) OR (c -d) y",
+                    ]
+                    .into_iter()
+                    .for_each(|query| {
+                        assert_eq!(
+                            parser.parse_with_span(query).unwrap().condition(),
+                            parser.parse(query).unwrap(),
+                            "{}",
+                            query
+                        );
+                        assert_eq!(
+                            parser.parse_strict(query),
+                            Ok(parser.parse(query).unwrap()),
+                            "{}",
+                            query
+                        )
+                    })
+                })
+        }
+    }
+
+    mod wildcard_query {
+        use super::*;
+
+        #[test]
+        fn test_prefix_and_wildcard() {
+            let actual = parse_query_to_condition("rust* te?t ru*st title:go* 5\\*").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Prefix("rust".into()),
+                        Condition::Wildcard("te?t".into()),
+                        Condition::Wildcard("ru*st".into()),
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Prefix("go".into()))
+                        ),
+                        Condition::Keyword("5*".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_prefix_and_wildcard_on_target() {
+            let actual = parse_query_to_condition_on_target("-title:go* body:(te?t)").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Not {
+                            condition: Box::new(ConditionOnTarget::Prefix {
+                                condition: "go".into(),
+                                target: Some(Target {
+                                    name: "title".into(),
+                                    weight: None
+                                }),
+                            }),
+                            target: Some(Target {
+                                name: "title".into(),
+                                weight: None
+                            }),
+                        },
+                        ConditionOnTarget::Wildcard {
+                            condition: "te?t".into(),
+                            target: Some(Target {
+                                name: "body".into(),
+                                weight: None
+                            }),
+                        },
+                    ],
+                    target: None,
+                }
+            )
+        }
+    }
+    mod fuzzy_query {
+        use super::*;
+
+        #[test]
+        fn test_fuzzy() {
+            let actual = parse_query_to_condition("rust~ title:go～1 rust~5 rust\\~1").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Fuzzy {
+                            term: "rust".into(),
+                            max_edits: 2
+                        },
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Fuzzy {
+                                term: "go".into(),
+                                max_edits: 1
+                            })
+                        ),
+                        Condition::Fuzzy {
+                            term: "rust".into(),
+                            max_edits: 2
+                        },
+                        Condition::Keyword("rust~1".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_fuzzy_on_target() {
+            let actual = parse_query_to_condition_on_target("body:(rust~0)").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Fuzzy {
+                    condition: "rust".into(),
+                    max_edits: 0,
+                    target: Some(Target {
+                        name: "body".into(),
+                        weight: None
+                    }),
+                }
+            )
+        }
+    }
+    mod proximity_query {
+        use super::*;
+
+        #[test]
+        fn test_proximity() {
+            let actual = parse_query_to_condition(
+                "\"quick fox\"~5 -title:\"lazy dog\"～1 \"a b\"~0 \"c\" ~2",
+            )
+            .unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Proximity {
+                            phrase: "quick fox".into(),
+                            slop: 5
+                        },
+                        Condition::Not(Box::new(Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Proximity {
+                                phrase: "lazy dog".into(),
+                                slop: 1
+                            })
+                        ))),
+                        Condition::PhraseKeyword("a b".into()),
+                        Condition::PhraseKeyword("c".into()),
+                        Condition::Keyword("~2".into()),
+                    ]
+                )
+            )
+        }
+    }
+    mod range_query {
+        use super::*;
+
+        #[test]
+        fn test_range() {
+            let actual =
+                parse_query_to_condition("price:[10 TO 100] OR -date:{2020-01-01 TO *}").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Range {
+                            target: "price".into(),
+                            lower: Some("10".into()),
+                            upper: Some("100".into()),
+                            inclusive_lower: true,
+                            inclusive_upper: true,
+                        },
+                        Condition::Not(Box::new(Condition::Range {
+                            target: "date".into(),
+                            lower: Some("2020-01-01".into()),
+                            upper: None,
+                            inclusive_lower: false,
+                            inclusive_upper: false,
+                        })),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_range_in_brackets() {
+            let actual = parse_query_to_condition("(price:[1 TO 2] rust)").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Range {
+                            target: "price".into(),
+                            lower: Some("1".into()),
+                            upper: Some("2".into()),
+                            inclusive_lower: true,
+                            inclusive_upper: true,
+                        },
+                        Condition::Keyword("rust".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_range_on_target() {
+            let actual = parse_query_to_condition_on_target("title:(price^2:[1 TO *])").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Range {
+                    lower: Some("1".into()),
+                    upper: None,
+                    inclusive_lower: true,
+                    inclusive_upper: true,
+                    target: Some(Target {
+                        name: "price".into(),
+                        weight: Some(2.0)
+                    }),
+                }
+            )
+        }
+    }
+    mod comparison_query {
+        use super::*;
+
+        #[test]
+        fn test_comparison() {
+            let actual = parse_query_to_condition("price:>=100 -stars:＜3 price:>").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Comparison {
+                            target: "price".into(),
+                            comparison: Comparison::GreaterThanOrEqual,
+                            value: "100".into(),
+                        },
+                        Condition::Not(Box::new(Condition::Comparison {
+                            target: "stars".into(),
+                            comparison: Comparison::LessThan,
+                            value: "3".into(),
+                        })),
+                        Condition::Targeted(
+                            "price".into(),
+                            Box::new(Condition::Keyword(">".into()))
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_comparison_on_target() {
+            let actual = parse_query_to_condition_on_target("price^2:>100").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Comparison {
+                    comparison: Comparison::GreaterThan,
+                    value: "100".into(),
+                    target: Some(Target {
+                        name: "price".into(),
+                        weight: Some(2.0)
+                    }),
+                }
+            )
+        }
+    }
+    mod not_query {
+        use super::*;
+
+        #[test]
+        fn test_not() {
+            let actual = parse_query_to_condition("NOT java AND NOT (x OR y) !rust").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::Keyword("java".into()))),
+                        Condition::Not(Box::new(Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Keyword("x".into()),
+                                Condition::Keyword("y".into())
+                            ]
+                        ))),
+                        Condition::Not(Box::new(Condition::Keyword("rust".into()))),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_not_binds_tighter_than_and_or() {
+            let actual = parse_query_to_condition("a OR NOT b c").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("a".into()),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Not(Box::new(Condition::Keyword("b".into()))),
+                                Condition::Keyword("c".into()),
+                            ]
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_not_negates_signed_keyword() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    [
+                        ("NOT -a", Condition::Keyword("a".into())),
+                        ("NOT -a*", Condition::Prefix("a".into())),
+                        ("NOT !a", Condition::Keyword("a".into())),
+                        ("NOT NOT a", Condition::Keyword("a".into())),
+                        ("NOT -\"a b\"", Condition::PhraseKeyword("a b".into())),
+                        (
+                            "NOT +a",
+                            Condition::Not(Box::new(Condition::Keyword("a".into()))),
+                        ),
+                        (
+                            "NOT -(a b)",
+                            Condition::Operator(
+                                Operator::And,
+                                vec![
+                                    Condition::Keyword("a".into()),
+                                    Condition::Keyword("b".into()),
+                                ],
+                            ),
+                        ),
+                    ]
+                    .into_iter()
+                    .for_each(|(query, expected)| {
+                        assert_eq!(
+                            parse_query_to_condition_with(query, approach).unwrap(),
+                            expected,
+                            "{} {:?}",
+                            query,
+                            approach
+                        )
+                    })
+                })
+        }
+    }
+    mod symbolic_operator_query {
+        use super::*;
+
+        #[test]
+        fn test_symbolic_operators() {
+            let actual = parse_query_to_condition("a && b || c & -d").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("a".into()),
+                                Condition::Keyword("b".into())
+                            ]
+                        ),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("c".into()),
+                                Condition::Not(Box::new(Condition::Keyword("d".into()))),
+                            ]
+                        ),
+                    ]
+                )
+            )
+        }
+    }
+    mod required_query {
+        use super::*;
+
+        #[test]
+        fn test_required_with_and_operator() {
+            let actual = parse_query_to_condition("+a b -c").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("a".into()),
+                        Condition::Keyword("b".into()),
+                        Condition::Not(Box::new(Condition::Keyword("c".into()))),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_required_with_or_operator() {
+            let parser = Parser::new().with_default_operator(DefaultOperator::Or);
+            assert_eq!(
+                parser.parse("+a b -c").unwrap(),
+                Condition::Boolean(vec![
+                    (Occur::Must, Condition::Keyword("a".into())),
+                    (Occur::Should, Condition::Keyword("b".into())),
+                    (Occur::MustNot, Condition::Keyword("c".into())),
+                ])
+            );
+            assert_eq!(
+                parser.parse("a b").unwrap(),
+                Condition::Boolean(vec![
+                    (Occur::Should, Condition::Keyword("a".into())),
+                    (Occur::Should, Condition::Keyword("b".into())),
+                ])
+            );
+            assert_eq!(parser.parse("+a").unwrap(), Condition::Keyword("a".into()));
+        }
+
+        #[test]
+        fn test_group_with_or_operator() {
+            let actual = Parser::new()
+                .with_default_operator(DefaultOperator::Or)
+                .parse("(+a b) c")
+                .unwrap();
+            assert_eq!(
+                actual,
+                Condition::Boolean(vec![
+                    (
+                        Occur::Should,
+                        Condition::Boolean(vec![
+                            (Occur::Must, Condition::Keyword("a".into())),
+                            (Occur::Should, Condition::Keyword("b".into())),
+                        ])
+                    ),
+                    (Occur::Should, Condition::Keyword("c".into())),
+                ])
+            )
+        }
+
+        #[test]
+        fn test_and_with_or_operator() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let parser = Parser::new()
+                        .with_approach(approach)
+                        .with_default_operator(DefaultOperator::Or);
+                    assert_eq!(
+                        parser.parse("a AND b c").unwrap(),
+                        Condition::Boolean(vec![
+                            (Occur::Must, Condition::Keyword("a".into())),
+                            (Occur::Must, Condition::Keyword("b".into())),
+                            (Occur::Should, Condition::Keyword("c".into())),
+                        ]),
+                        "{:?}",
+                        approach
+                    );
+                    assert_eq!(
+                        parser.parse("a AND -b").unwrap(),
+                        Condition::Boolean(vec![
+                            (Occur::Must, Condition::Keyword("a".into())),
+                            (Occur::MustNot, Condition::Keyword("b".into())),
+                        ]),
+                        "{:?}",
+                        approach
+                    );
+                    assert_eq!(
+                        parser.parse("a AND b").unwrap(),
+                        parser.parse("+a +b").unwrap(),
+                        "{:?}",
+                        approach
+                    );
+                    assert_eq!(
+                        parser.parse("AND a b AND").unwrap(),
+                        Condition::Boolean(vec![
+                            (Occur::Should, Condition::Keyword("a".into())),
+                            (Occur::Should, Condition::Keyword("b".into())),
+                        ]),
+                        "{:?}",
+                        approach
+                    );
+                })
+        }
+    }
+    mod escaped_query {
+        use super::*;
+
+        #[test]
+        fn test_escaped_reserved_characters() {
+            let actual = parse_query_to_condition("\\(a\\) \\-b \\OR \"c \\\" d\" e\\ f").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("(a)".into()),
+                        Condition::Keyword("-b".into()),
+                        Condition::Keyword("OR".into()),
+                        Condition::PhraseKeyword("c \" d".into()),
+                        Condition::Keyword("e f".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_escaped_colon_is_not_target() {
+            let actual = parse_query_to_condition_on_target("a\\:b title:c\\:d").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Keyword {
+                            condition: "a:b".into(),
+                            target: None,
+                        },
+                        ConditionOnTarget::Keyword {
+                            condition: "c:d".into(),
+                            target: Some(Target {
+                                name: "title".into(),
+                                weight: None,
+                            }),
+                        },
+                    ],
+                    target: None,
+                }
+            )
+        }
+
+        #[test]
+        fn test_escape_term() {
+            vec!["a (b) \"c\" OR -d:e", "AND", "x* y~ \\z", "+!&&||"]
+                .into_iter()
+                .for_each(|text| {
+                    let query = format!("title:{}", escape_term(text));
+                    [Approach::Regex, Approach::Bnf]
+                        .into_iter()
+                        .for_each(|approach| {
+                            assert_eq!(
+                                parse_query_to_condition_on_target_with(&query, approach).unwrap(),
+                                ConditionOnTarget::Keyword {
+                                    condition: text.into(),
+                                    target: Some(Target {
+                                        name: "title".into(),
+                                        weight: None,
+                                    }),
+                                },
+                                "{:?} {}",
+                                approach,
+                                query
+                            )
+                        })
+                })
+        }
+
+        // every string of one or two characters, and the pseudo random ones which are longer
+        #[test]
+        fn test_escape_term_round_trip() {
+            let chars =
+                "\\()（）\"”-+!！:：*?~～&＆|｜[]{}<>＜＞=＝^/／ 　aTO\u{E000}\u{F0028}\u{100041}"
+                    .chars()
+                    .collect::<Vec<char>>();
+            let mut texts = chars
+                .iter()
+                .flat_map(|a| {
+                    std::iter::once(a.to_string())
+                        .chain(chars.iter().map(move |b| format!("{}{}", a, b)))
+                })
+                .collect::<Vec<String>>();
+            let mut seed = 1_u64;
+            texts.extend((0..2000).map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (0..3 + (seed >> 60) % 5)
+                    .map(|k| chars[(seed.rotate_left(k as u32 * 7) >> 33) as usize % chars.len()])
+                    .collect()
+            }));
+            texts
+                .into_iter()
+                .filter(|text| !text.trim().is_empty())
+                .for_each(|text| {
+                    [Approach::Regex, Approach::Bnf]
+                        .into_iter()
+                        .for_each(|approach| {
+                            assert_eq!(
+                                parse_query_to_condition_with(&escape_term(&text), approach)
+                                    .unwrap(),
+                                Condition::Keyword(text.clone()),
+                                "{:?} {:?}",
+                                approach,
+                                text
+                            );
+                            assert_eq!(
+                                parse_query_to_condition_with(
+                                    &format!("title:{}", escape_term(&text)),
+                                    approach
+                                )
+                                .unwrap(),
+                                Condition::Targeted(
+                                    "title".into(),
+                                    Box::new(Condition::Keyword(text.clone()))
+                                ),
+                                "{:?} {:?}",
+                                approach,
+                                text
+                            )
+                        })
+                })
+        }
+
+        // the private use characters typed in the query are not taken as the escaped characters
+        #[test]
+        fn test_private_use_characters() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with(
+                            "\u{F0028}a\u{F0029}\\ b \u{100041}x \u{E000}\\(",
+                            approach
+                        )
+                        .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("\u{F0028}a\u{F0029} b".into()),
+                                Condition::Keyword("\u{100041}x".into()),
+                                Condition::Keyword("\u{E000}(".into()),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+    }
+
+    mod regex_query {
+        use super::*;
+
+        #[test]
+        fn test_regex() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with(
+                            "/err(or)?\\d+/ -title:/a b/ /c\\/d/ \\/e/ /(/",
+                            approach
+                        )
+                        .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Regex("err(or)?\\d+".into()),
+                                Condition::Not(Box::new(Condition::Targeted(
+                                    "title".into(),
+                                    Box::new(Condition::Regex("a b".into()))
+                                ))),
+                                Condition::Regex("c/d".into()),
+                                Condition::Keyword("/e/".into()),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_regex_on_target() {
+            let actual = parse_query_to_condition_on_target("log:(/a.*/ OR b)").unwrap();
+            assert_eq!(
+                actual,
+                ConditionOnTarget::Operator {
+                    operator: Operator::Or,
+                    conditions: vec![
+                        ConditionOnTarget::Regex {
+                            condition: "a.*".into(),
+                            target: Some(Target {
+                                name: "log".into(),
+                                weight: None,
+                            }),
+                        },
+                        ConditionOnTarget::Keyword {
+                            condition: "b".into(),
+                            target: Some(Target {
+                                name: "log".into(),
+                                weight: None,
+                            }),
+                        },
+                    ],
+                    target: Some(Target {
+                        name: "log".into(),
+                        weight: None,
+                    }),
+                }
+            )
+        }
+    }
+
+    mod exists_query {
+        use super::*;
+
+        #[test]
+        fn test_exists() {
+            let actual =
+                parse_query_to_condition("_exists_:title -_exists_:author has:body").unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Exists {
+                            target: "title".into()
+                        },
+                        Condition::Not(Box::new(Condition::Exists {
+                            target: "author".into()
+                        })),
+                        Condition::Targeted(
+                            "has".into(),
+                            Box::new(Condition::Keyword("body".into()))
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_exists_with_alias() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let parser = Parser::new()
+                        .with_approach(approach)
+                        .with_exists_alias("has");
+                    assert_eq!(
+                        parser
+                            .parse_on_target("has:attachment -has:title^2")
+                            .unwrap(),
+                        ConditionOnTarget::Operator {
+                            operator: Operator::And,
+                            conditions: vec![
+                                ConditionOnTarget::Exists {
+                                    target: Some(Target {
+                                        name: "attachment".into(),
+                                        weight: None,
+                                    }),
+                                },
+                                ConditionOnTarget::Not {
+                                    condition: Box::new(ConditionOnTarget::Exists {
+                                        target: Some(Target {
+                                            name: "title".into(),
+                                            weight: Some(2.0),
+                                        }),
+                                    }),
+                                    target: Some(Target {
+                                        name: "title".into(),
+                                        weight: Some(2.0),
+                                    }),
+                                },
+                            ],
+                            target: None,
+                        },
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+    }
+
+    mod near_query {
+        use super::*;
+
+        #[test]
+        fn test_near() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with(
+                            "contract ＮＥＡＲ／５ breach OR damages onear/2 (court OR tribunal)",
+                            approach
+                        )
+                        .unwrap(),
+                        Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Operator(
+                                    Operator::Near(5),
+                                    vec![
+                                        Condition::Keyword("contract".into()),
+                                        Condition::Keyword("breach".into()),
+                                    ]
+                                ),
+                                Condition::Operator(
+                                    Operator::OrderedNear(2),
+                                    vec![
+                                        Condition::Keyword("damages".into()),
+                                        Condition::Operator(
+                                            Operator::Or,
+                                            vec![
+                                                Condition::Keyword("court".into()),
+                                                Condition::Keyword("tribunal".into()),
+                                            ]
+                                        ),
+                                    ]
+                                ),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_near_has_higher_priority_than_and() {
+            assert_eq!(
+                parse_query_to_condition("a b NEAR/3 c NEAR/3 d NEAR").unwrap(),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("a".into()),
+                        Condition::Operator(
+                            Operator::Near(3),
+                            vec![
+                                Condition::Keyword("b".into()),
+                                Condition::Keyword("c".into()),
+                                Condition::Keyword("d".into()),
+                            ]
+                        ),
+                        Condition::Keyword("NEAR".into()),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_dangling_near() {
+            assert_eq!(
+                parse_query_strict("a NEAR/3 OR b"),
+                Err(SyntaxError::DanglingOperator(Span { start: 2, end: 8 }))
+            )
+        }
+    }
+
+    mod boost_query {
+        use super::*;
+
+        fn boosted(condition: Condition, boost: f32) -> Condition {
+            Condition::Boosted {
+                condition: Box::new(condition),
+                boost: Boost::new(boost).unwrap(),
+            }
+        }
+
+        #[test]
+        fn test_boost() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with(
+                            "rust^2 \"exact phrase\"^1.5 -title:(a OR b)^0.5",
+                            approach
+                        )
+                        .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                boosted(Condition::Keyword("rust".into()), 2.0),
+                                boosted(Condition::PhraseKeyword("exact phrase".into()), 1.5),
+                                Condition::Not(Box::new(Condition::Targeted(
+                                    "title".into(),
+                                    Box::new(boosted(
+                                        Condition::Operator(
+                                            Operator::Or,
+                                            vec![
+                                                Condition::Keyword("a".into()),
+                                                Condition::Keyword("b".into()),
+                                            ]
+                                        ),
+                                        0.5
+                                    ))
+                                ))),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_invalid_boost_is_keyword() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with("a^NaN b^inf c^-1 (d)^2x ^2", approach)
+                            .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("a^NaN".into()),
+                                Condition::Keyword("b^inf".into()),
+                                Condition::Keyword("c^-1".into()),
+                                Condition::Keyword("d".into()),
+                                Condition::Keyword("^2x".into()),
+                                Condition::Keyword("^2".into()),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        // the blank phrase and brackets are removed like they have never been typed
+        #[test]
+        fn test_boost_after_blank_phrase_is_keyword() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    assert_eq!(
+                        parse_query_to_condition_with(
+                            "a \"\"^b -\" \"~2^2 title:\"\"^3 ()^4",
+                            approach
+                        )
+                        .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("a".into()),
+                                Condition::Keyword("^b".into()),
+                                Condition::Keyword("^2".into()),
+                                Condition::Keyword("^3".into()),
+                                Condition::Keyword("^4".into()),
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    )
+                })
+        }
+
+        #[test]
+        fn test_boost_on_target() {
+            assert_eq!(
+                parse_query_to_condition_on_target("title^2:rust^0.5").unwrap(),
+                ConditionOnTarget::Boosted {
+                    condition: Box::new(ConditionOnTarget::Keyword {
+                        condition: "rust".into(),
+                        target: Some(Target {
+                            name: "title".into(),
+                            weight: Some(2.0),
+                        }),
+                    }),
+                    boost: Boost::new(0.5).unwrap(),
+                    target: Some(Target {
+                        name: "title".into(),
+                        weight: Some(2.0),
+                    }),
+                }
+            )
+        }
+
+        #[test]
+        fn test_boost_round_trip() {
+            let condition =
+                parse_query_to_condition("rust^2 -title:\"a b\"~1^0.5 (c OR d)^3").unwrap();
+            assert_eq!(
+                parse_query_to_condition(&condition.to_query_string()).unwrap(),
+                condition
+            )
+        }
+    }
 }
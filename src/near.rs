@@ -0,0 +1,111 @@
+use crate::{ConditionOnTarget, Operator};
+use regex::Regex;
+use std::sync::LazyLock;
+
+// `NEAR/5` and `ONEAR/5` for the ordered one, half or full width like `AND` and `OR`
+static REGEX_NEAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new("^(?i)(?P<ordered>[OＯ])?[NＮ][EＥ][AＡ][RＲ][/／](?P<distance>[0-9０-９]+)$")
+        .unwrap()
+});
+
+// the operator of a word between keywords, `NEAR` without the distance is a keyword
+pub(crate) fn near_operator(word: &str) -> Option<Operator> {
+    let captures = REGEX_NEAR.captures(word)?;
+    let distance = captures["distance"]
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            c => c,
+        })
+        .collect::<String>()
+        .parse::<u32>()
+        .ok()?;
+    match captures.name("ordered") {
+        Some(_) => Some(Operator::OrderedNear(distance)),
+        None => Some(Operator::Near(distance)),
+    }
+}
+
+impl Operator {
+    // the max number of the tokens between the terms, and whether the terms are in order
+    pub(crate) fn near(&self) -> Option<(u32, bool)> {
+        match self {
+            Operator::Near(distance) => Some((*distance, false)),
+            Operator::OrderedNear(distance) => Some((*distance, true)),
+            Operator::And | Operator::Or => None,
+        }
+    }
+
+    // how the operator is written between the conditions
+    pub(crate) fn word(&self) -> String {
+        match self {
+            Operator::And => "AND".into(),
+            Operator::Or => "OR".into(),
+            Operator::Near(distance) => format!("NEAR/{}", distance),
+            Operator::OrderedNear(distance) => format!("ONEAR/{}", distance),
+        }
+    }
+}
+
+impl ConditionOnTarget {
+    // whether the positions of the matched tokens are known, `NEAR/n` with a term without them
+    // like `-a` or a range is the same as `AND` in both the query dsl and the evaluator
+    pub(crate) fn has_positions(&self) -> bool {
+        match self {
+            ConditionOnTarget::Keyword { .. }
+            | ConditionOnTarget::PhraseKeyword { .. }
+            | ConditionOnTarget::Proximity { .. }
+            | ConditionOnTarget::Prefix { .. }
+            | ConditionOnTarget::Wildcard { .. }
+            | ConditionOnTarget::Regex { .. }
+            | ConditionOnTarget::Fuzzy { .. } => true,
+            ConditionOnTarget::Boosted { condition, .. } => condition.has_positions(),
+            ConditionOnTarget::Operator {
+                operator,
+                conditions,
+                ..
+            } => {
+                (operator == &Operator::Or || operator.near().is_some())
+                    && conditions.iter().all(ConditionOnTarget::has_positions)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod test_near_operator {
+        use super::*;
+
+        #[test]
+        fn test_near_operator() {
+            vec![
+                ("NEAR/5", Some(Operator::Near(5))),
+                ("near/0", Some(Operator::Near(0))),
+                ("ＮＥＡＲ／１２", Some(Operator::Near(12))),
+                ("ONEAR/3", Some(Operator::OrderedNear(3))),
+                ("ｏｎｅａｒ/3", Some(Operator::OrderedNear(3))),
+                ("NEAR", None),
+                ("NEAR/", None),
+                ("NEAR/x", None),
+                ("NEARS/5", None),
+                ("NEAR/99999999999", None),
+            ]
+            .into_iter()
+            .for_each(|(word, expected)| assert_eq!(near_operator(word), expected, "{}", word))
+        }
+
+        #[test]
+        fn test_word() {
+            assert_eq!(Operator::Or.word(), "OR");
+            assert_eq!(Operator::Near(5).word(), "NEAR/5");
+            assert_eq!(
+                near_operator(&Operator::OrderedNear(3).word()),
+                Some(Operator::OrderedNear(3))
+            )
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{Condition, DefaultOperator, Occur, Operator};
+
+impl Condition {
+    // `+A`, which is a must clause alone until the default operator is applied
+    pub(crate) fn required(condition: Condition) -> Self {
+        Condition::Boolean(vec![(Occur::Must, condition)])
+    }
+
+    // `A AND B` is `+A +B` with `DefaultOperator::Or`, and the same as `A B` with `DefaultOperator::And`,
+    // `-A` is already a must not clause
+    pub(crate) fn required_by_and(self) -> Self {
+        match &self {
+            Condition::Not(_) => self,
+            Condition::Boolean(clauses) if matches!(clauses.as_slice(), [(Occur::Must, _)]) => self,
+            _ => Condition::required(self),
+        }
+    }
+
+    // `(A -B)`, which is a should clause alone not to be flattened into the conditions around it by simplify,
+    // because `(A -B) C` is not the same as `A -B C` with `Operator::Or`
+    pub(crate) fn group(condition: Condition) -> Self {
+        Condition::Boolean(vec![(Occur::Should, condition)])
+    }
+
+    // with `Operator::Or`, the conditions joined by spaces or `AND` are should clauses unless `+` or `-` is before them,
+    // and with `Operator::And`, `+A` is the same as `A`
+    pub(crate) fn apply_default_operator(self, default_operator: DefaultOperator) -> Self {
+        match self {
+            Condition::Operator(Operator::And, conditions)
+                if default_operator == DefaultOperator::Or =>
+            {
+                Condition::Boolean(
+                    conditions
+                        .into_iter()
+                        .map(|condition| condition.into_clause(default_operator))
+                        .collect(),
+                )
+            }
+            Condition::Operator(operator, conditions) => Condition::Operator(
+                operator,
+                conditions
+                    .into_iter()
+                    .map(|condition| condition.apply_default_operator(default_operator))
+                    .collect(),
+            ),
+            Condition::Boolean(clauses) => into_operators(
+                clauses
+                    .into_iter()
+                    .map(|(occur, condition)| {
+                        (occur, condition.apply_default_operator(default_operator))
+                    })
+                    .collect(),
+            ),
+            Condition::Not(condition) => {
+                Condition::Not(Box::new(condition.apply_default_operator(default_operator)))
+            }
+            Condition::Targeted(target, condition) => Condition::Targeted(
+                target,
+                Box::new(condition.apply_default_operator(default_operator)),
+            ),
+            Condition::Boosted { condition, boost } => Condition::Boosted {
+                condition: Box::new(condition.apply_default_operator(default_operator)),
+                boost,
+            },
+            condition => condition,
+        }
+    }
+
+    // a group is kept only when it has `-` or `+` in it, the other groups are the same without brackets
+    pub(crate) fn is_kept_group(&self) -> bool {
+        match self {
+            Condition::Operator(Operator::And, conditions) => {
+                conditions.iter().any(|condition| match condition {
+                    Condition::Not(_) => true,
+                    Condition::Boolean(clauses) => {
+                        clauses.iter().any(|(occur, _)| occur != &Occur::Should)
+                    }
+                    _ => false,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    // the brackets are meaningful only in `AND`, where the group is not flattened,
+    // in `OR` and `NEAR/n` the group is the same as its condition
+    pub(crate) fn ungroup(self) -> Self {
+        match self {
+            Condition::Boolean(mut clauses)
+                if matches!(clauses.as_slice(), [(Occur::Should, _)]) =>
+            {
+                clauses.remove(0).1
+            }
+            condition => condition,
+        }
+    }
+
+    fn into_clause(self, default_operator: DefaultOperator) -> (Occur, Condition) {
+        match self {
+            Condition::Not(condition) => (
+                Occur::MustNot,
+                condition.apply_default_operator(default_operator),
+            ),
+            Condition::Boolean(mut clauses) if clauses.len() == 1 => {
+                let (occur, condition) = clauses.remove(0);
+                (occur, condition.apply_default_operator(default_operator))
+            }
+            condition => (
+                Occur::Should,
+                condition.apply_default_operator(default_operator),
+            ),
+        }
+    }
+}
+
+// the same matching with `AND`, `OR` and `-`, the should clauses are ignored when there is a must clause
+fn into_operators(clauses: Vec<(Occur, Condition)>) -> Condition {
+    let has_must = clauses.iter().any(|(occur, _)| occur == &Occur::Must);
+    let (shoulds, others): (Vec<_>, Vec<_>) = clauses
+        .into_iter()
+        .partition(|(occur, _)| occur == &Occur::Should);
+    let mut conditions = others
+        .into_iter()
+        .map(|(occur, condition)| match occur {
+            Occur::MustNot => Condition::Not(Box::new(condition)),
+            _ => condition,
+        })
+        .collect::<Vec<Condition>>();
+    if !has_must {
+        conditions.push(Condition::Operator(
+            Operator::Or,
+            shoulds
+                .into_iter()
+                .map(|(_, condition)| condition)
+                .collect(),
+        ))
+    }
+    Condition::Operator(Operator::And, conditions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyword(keyword: &str) -> Condition {
+        Condition::Keyword(keyword.into())
+    }
+
+    mod test_apply_default_operator {
+        use super::*;
+
+        #[test]
+        fn test_apply_and() {
+            let condition = Condition::Operator(
+                Operator::And,
+                vec![
+                    Condition::required(keyword("a")),
+                    keyword("b"),
+                    Condition::Not(Box::new(keyword("c"))),
+                ],
+            );
+            assert_eq!(
+                condition
+                    .apply_default_operator(DefaultOperator::And)
+                    .simplify(),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        keyword("a"),
+                        keyword("b"),
+                        Condition::Not(Box::new(keyword("c")))
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_apply_or() {
+            let condition = Condition::Operator(
+                Operator::Or,
+                vec![
+                    Condition::Operator(
+                        Operator::And,
+                        vec![
+                            Condition::required(keyword("a")),
+                            keyword("b"),
+                            Condition::Not(Box::new(keyword("c"))),
+                        ],
+                    ),
+                    Condition::required(keyword("d")),
+                ],
+            );
+            assert_eq!(
+                condition
+                    .apply_default_operator(DefaultOperator::Or)
+                    .simplify(),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Boolean(vec![
+                            (Occur::Must, keyword("a")),
+                            (Occur::Should, keyword("b")),
+                            (Occur::MustNot, keyword("c")),
+                        ]),
+                        keyword("d"),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_into_operators() {
+            assert_eq!(
+                into_operators(vec![
+                    (Occur::Should, keyword("a")),
+                    (Occur::MustNot, keyword("b")),
+                    (Occur::Should, keyword("c")),
+                ])
+                .simplify(),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(keyword("b"))),
+                        Condition::Operator(Operator::Or, vec![keyword("a"), keyword("c")]),
+                    ]
+                )
+            );
+            assert_eq!(
+                into_operators(vec![
+                    (Occur::Should, keyword("a")),
+                    (Occur::Must, keyword("b"))
+                ])
+                .simplify(),
+                keyword("b")
+            )
+        }
+    }
+}
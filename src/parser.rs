@@ -0,0 +1,204 @@
+use crate::escape::EscapedQuery;
+use crate::query_string::write_query_string;
+use crate::regex_approach::layered_query::LayeredQueries;
+use crate::regex_approach::query::Query;
+use crate::{
+    bnf_approach, Approach, Condition, ConditionOnTarget, DefaultOperator, ParsedQuery, Parser,
+    SpannedCondition, SyntaxError, Warning,
+};
+use eyre::Result;
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            approach: Approach::default(),
+            symbolic_operators: true,
+            default_operator: DefaultOperator::And,
+            exists_alias: None,
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_approach(mut self, approach: Approach) -> Self {
+        self.approach = approach;
+        self
+    }
+
+    // `&&`, `&`, `||` and `|` are parsed as `AND` and `OR` unless they are disabled
+    pub fn with_symbolic_operators(mut self, symbolic_operators: bool) -> Self {
+        self.symbolic_operators = symbolic_operators;
+        self
+    }
+
+    // with `DefaultOperator::Or`, the keywords are optional unless `+` is before them or `AND` is next to them like lucene,
+    // and they are parsed to `Condition::Boolean` with the must, should and must not clauses
+    pub fn with_default_operator(mut self, default_operator: DefaultOperator) -> Self {
+        self.default_operator = default_operator;
+        self
+    }
+
+    // `has:title` is parsed as `_exists_:title` with the alias `has`
+    pub fn with_exists_alias(mut self, alias: &str) -> Self {
+        self.exists_alias = Some(alias.into());
+        self
+    }
+
+    pub fn parse(&self, query: &str) -> Result<Condition> {
+        let condition = match self.approach {
+            Approach::Regex => {
+                let escaped = EscapedQuery::new(query);
+                LayeredQueries::parse(
+                    Query::new(escaped.query),
+                    self.symbolic_operators,
+                    &escaped.escapes,
+                )?
+                .to_condition(&escaped.escapes, self.exists_alias.as_deref())?
+            }
+            Approach::Bnf => {
+                bnf_approach::parse(query, self.symbolic_operators, self.exists_alias.as_deref())
+            }
+        };
+        Ok(condition
+            .apply_default_operator(self.default_operator)
+            .simplify())
+    }
+
+    pub fn parse_on_target(&self, query: &str) -> Result<ConditionOnTarget> {
+        Ok(self.parse(query)?.identify_target())
+    }
+
+    // spans are only available with the bnf approach, the regex approach rewrites the query with placeholders
+    pub fn parse_with_span(&self, query: &str) -> Result<SpannedCondition> {
+        Ok(self.parse_with_errors(query).0)
+    }
+
+    // what is corrected in the query is reported as warnings, the condition is from the same parse as them
+    pub fn parse_with_warnings(&self, query: &str) -> Result<ParsedQuery> {
+        let (spanned, errors) = self.parse_with_errors(query);
+        Ok(ParsedQuery {
+            condition: spanned.condition(),
+            warnings: errors
+                .into_iter()
+                .map(|error| Warning {
+                    text: query[error.span().start..error.span().end].into(),
+                    error,
+                })
+                .collect(),
+        })
+    }
+
+    // the queries which would be corrected are rejected with the leftmost error in them
+    pub fn parse_strict(&self, query: &str) -> std::result::Result<Condition, SyntaxError> {
+        let (spanned, errors) = self.parse_with_errors(query);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(spanned.condition()),
+        }
+    }
+
+    // the same as `Condition::to_query_string`, and the target named like the alias of `_exists_` is escaped
+    pub fn to_query_string(&self, condition: &Condition) -> String {
+        write_query_string(condition, self.exists_alias.as_deref())
+    }
+
+    // parsed by the bnf approach whichever the approach is, with the other settings the same as `parse`
+    fn parse_with_errors(&self, query: &str) -> (SpannedCondition, Vec<SyntaxError>) {
+        let (spanned, errors) = bnf_approach::parse_with_errors(
+            query,
+            self.symbolic_operators,
+            self.exists_alias.as_deref(),
+        );
+        (
+            spanned
+                .apply_default_operator(self.default_operator)
+                .simplify(),
+            errors,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Operator;
+    use std::thread;
+
+    mod test_parser {
+        use super::*;
+
+        #[test]
+        fn test_parser_is_send_and_sync() {
+            fn assert_send_and_sync<T: Send + Sync>() {}
+            assert_send_and_sync::<Parser>();
+        }
+
+        #[test]
+        fn test_parser_without_symbolic_operators() {
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let parser = Parser::new().with_approach(approach);
+                    assert_eq!(
+                        parser.parse("a || b").unwrap(),
+                        Condition::Operator(
+                            Operator::Or,
+                            vec![
+                                Condition::Keyword("a".into()),
+                                Condition::Keyword("b".into())
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    );
+                    assert_eq!(
+                        parser
+                            .with_symbolic_operators(false)
+                            .parse("a || b")
+                            .unwrap(),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("a".into()),
+                                Condition::Keyword("||".into()),
+                                Condition::Keyword("b".into())
+                            ]
+                        ),
+                        "{:?}",
+                        approach
+                    );
+                })
+        }
+
+        #[test]
+        fn test_parser_shared_between_threads() {
+            let parser = Parser::new().with_approach(Approach::Bnf);
+            thread::scope(|scope| {
+                (0..4)
+                    .map(|i| {
+                        let parser = &parser;
+                        scope.spawn(move || parser.parse(&format!("Ａ{} OR Ｂ", i)).unwrap())
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .enumerate()
+                    .for_each(|(i, handle)| {
+                        assert_eq!(
+                            handle.join().unwrap(),
+                            Condition::Operator(
+                                Operator::Or,
+                                vec![
+                                    Condition::Keyword(format!("Ａ{}", i)),
+                                    Condition::Keyword("Ｂ".into()),
+                                ]
+                            )
+                        )
+                    })
+            })
+        }
+    }
+}
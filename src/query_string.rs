@@ -0,0 +1,497 @@
+use crate::escape::{escape_keyword, escape_phrase};
+use crate::term::EXISTS_TARGET;
+use crate::{Comparison, Condition, Occur, Operator};
+use std::fmt::{Display, Formatter};
+
+// where the condition is written, to decide if the brackets are necessary
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Position {
+    Top,
+    InAnd,
+    InOr,
+    InNear,
+    // after `-` or `target:`
+    Unary,
+}
+
+// the signs and colons of the dates and numbers like `2020-01-01T00:00:00+09:00` are not escaped
+// in the bounds of a range and the value of a comparison
+const VALUE_CHARS: [char; 4] = ['-', '+', ':', '：'];
+
+impl Condition {
+    // the minimal query which is parsed to the same condition after simplify,
+    // with `Operator::Or` as the default operator when it has `Condition::Boolean`
+    pub fn to_query_string(&self) -> String {
+        write_query_string(self, None)
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_query_string())
+    }
+}
+
+// the target named like the alias of `_exists_` is escaped, so that it's parsed back to the target
+pub(crate) fn write_query_string(condition: &Condition, exists_alias: Option<&str>) -> String {
+    write_condition(condition, Position::Top, exists_alias)
+}
+
+fn write_condition(
+    condition: &Condition, position: Position, exists_alias: Option<&str>,
+) -> String {
+    match condition {
+        Condition::None => "".into(),
+        Condition::Keyword(keyword) => escape_keyword(keyword, &[]),
+        Condition::Prefix(prefix) => format!("{}*", escape_keyword(prefix, &[])),
+        // the wildcard keeps the escapes of its own
+        Condition::Wildcard(wildcard) => escape_keyword(wildcard, &['\\', '*', '?']),
+        Condition::Fuzzy { term, max_edits } => {
+            format!("{}~{}", escape_keyword(term, &[]), max_edits)
+        }
+        Condition::PhraseKeyword(phrase_keyword) => {
+            format!("\"{}\"", escape_phrase(phrase_keyword))
+        }
+        Condition::Proximity { phrase, slop } => format!("\"{}\"~{}", escape_phrase(phrase), slop),
+        Condition::Regex(pattern) => format!("/{}/", pattern.replace('/', "\\/")),
+        Condition::Range {
+            target,
+            lower,
+            upper,
+            inclusive_lower,
+            inclusive_upper,
+        } => format!(
+            "{}:{}{} TO {}{}",
+            write_target(target, exists_alias),
+            if *inclusive_lower { "[" } else { "{" },
+            lower
+                .as_deref()
+                .map_or("*".into(), |lower| escape_keyword(lower, &VALUE_CHARS)),
+            upper
+                .as_deref()
+                .map_or("*".into(), |upper| escape_keyword(upper, &VALUE_CHARS)),
+            if *inclusive_upper { "]" } else { "}" },
+        ),
+        Condition::Comparison {
+            target,
+            comparison,
+            value,
+        } => format!(
+            "{}:{}{}",
+            write_target(target, exists_alias),
+            match comparison {
+                Comparison::GreaterThan => ">",
+                Comparison::GreaterThanOrEqual => ">=",
+                Comparison::LessThan => "<",
+                Comparison::LessThanOrEqual => "<=",
+            },
+            escape_keyword(value, &VALUE_CHARS)
+        ),
+        Condition::Exists { target } => format!("_exists_:{}", escape_keyword(target, &[])),
+        Condition::Not(condition) => match (
+            condition.as_ref(),
+            write_condition(condition, Position::Unary, exists_alias),
+        ) {
+            (_, written) if written.is_empty() => written,
+            // `--A` is a negative keyword `-A`
+            (Condition::Not(_), written) => format!("-({})", written),
+            (_, written) => format!("-{}", written),
+        },
+        Condition::Targeted(target, condition) => {
+            // the weight of `title^2` is a part of the target
+            let target = write_target(target, exists_alias);
+            match condition.as_ref() {
+                Condition::Keyword(_)
+                | Condition::Prefix(_)
+                | Condition::Wildcard(_)
+                | Condition::Fuzzy { .. }
+                | Condition::PhraseKeyword(_)
+                | Condition::Proximity { .. }
+                | Condition::Regex(_) => {
+                    format!(
+                        "{}:{}",
+                        target,
+                        write_condition(condition, Position::Unary, exists_alias)
+                    )
+                }
+                // `title:a^2` and `title:(a b)^2` are boosted on the target
+                Condition::Boosted {
+                    condition: boosted, ..
+                } if matches!(
+                    boosted.as_ref(),
+                    Condition::Keyword(_)
+                        | Condition::Prefix(_)
+                        | Condition::Wildcard(_)
+                        | Condition::Fuzzy { .. }
+                        | Condition::PhraseKeyword(_)
+                        | Condition::Proximity { .. }
+                        | Condition::Regex(_)
+                        | Condition::Operator(..)
+                        | Condition::Boolean(_)
+                ) =>
+                {
+                    match write_condition(condition, Position::Unary, exists_alias) {
+                        written if written.is_empty() => written,
+                        written => format!("{}:{}", target, written),
+                    }
+                }
+                _ => match write_condition(condition, Position::Top, exists_alias) {
+                    written if written.is_empty() => written,
+                    written => format!("{}:({})", target, written),
+                },
+            }
+        }
+        Condition::Operator(operator, conditions) => {
+            let (separator, child_position) = match operator {
+                Operator::And => (" ".into(), Position::InAnd),
+                Operator::Or => (" OR ".into(), Position::InOr),
+                operator => (format!(" {} ", operator.word()), Position::InNear),
+            };
+            let written = conditions
+                .iter()
+                .map(|condition| write_condition(condition, child_position, exists_alias))
+                .filter(|written| !written.is_empty())
+                .collect::<Vec<String>>();
+            let needs_brackets = written.len() > 1
+                && matches!(
+                    (operator, position),
+                    (_, Position::Unary | Position::InNear) | (Operator::Or, Position::InAnd)
+                );
+            match needs_brackets {
+                true => format!("({})", written.join(&separator)),
+                false => written.join(&separator),
+            }
+        }
+        Condition::Boolean(clauses) => {
+            let written = clauses
+                .iter()
+                .map(|(occur, condition)| match occur {
+                    Occur::Must => {
+                        match write_condition(condition, Position::Unary, exists_alias) {
+                            written if written.is_empty() => written,
+                            written => format!("+{}", written),
+                        }
+                    }
+                    Occur::Should => write_condition(condition, Position::InAnd, exists_alias),
+                    Occur::MustNot => write_condition(
+                        &Condition::Not(Box::new(condition.clone())),
+                        Position::InAnd,
+                        exists_alias,
+                    ),
+                })
+                .filter(|written| !written.is_empty())
+                .collect::<Vec<String>>();
+            match position != Position::Top && written.len() > 1 {
+                true => format!("({})", written.join(" ")),
+                false => written.join(" "),
+            }
+        }
+        Condition::Boosted { condition, boost } => match (
+            condition.as_ref(),
+            write_condition(condition, Position::Unary, exists_alias),
+        ) {
+            (_, written) if written.is_empty() => written,
+            // the boost after `-a`, `title:a` or a range would be taken as the one of the term
+            (
+                Condition::Not(_)
+                | Condition::Targeted(..)
+                | Condition::Boosted { .. }
+                | Condition::Range { .. }
+                | Condition::Comparison { .. }
+                | Condition::Exists { .. },
+                written,
+            ) => format!("({})^{}", written, boost),
+            (_, written) => format!("{}^{}", written, boost),
+        },
+    }
+}
+
+// the weight of `title^2` is a part of the target, and the target named `_exists_` or its alias is escaped
+fn write_target(target: &str, exists_alias: Option<&str>) -> String {
+    let written = escape_keyword(target, &['^']);
+    match (target == EXISTS_TARGET || Some(target) == exists_alias) && !written.starts_with('\\') {
+        true => format!("\\{}", written),
+        false => written,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_query_to_condition_with, Approach, Boost, DefaultOperator, Parser};
+
+    mod test_to_query_string {
+        use super::*;
+
+        #[test]
+        fn test_to_query_string_canonical() {
+            vec![
+                ("", ""),
+                ("Ａ１　Ａ２", "Ａ１ Ａ２"),
+                ("a or b and c", "a OR b c"),
+                ("((a OR (b OR c))) AND (d)", "(a OR b OR c) d"),
+                ("a OR b (c OR d)", "a OR b (c OR d)"),
+                ("a OR b -c title:\"x y\"", "a OR b -c title:\"x y\""),
+                ("-(a b) -(c OR d) -\"e\"", "-(a b) -(c OR d) -\"e\""),
+                (
+                    "title:(a OR \"b c\") body:(d)",
+                    "title:(a OR \"b c\") body:d",
+                ),
+                ("a () \"\" AND OR b)", "a OR b"),
+                (
+                    "rust* te?t ru\\*st title:go*",
+                    "rust* te?t ru\\*st title:go*",
+                ),
+                ("rust~ title:go～1 ru\\~st~9", "rust~2 title:go~1 ru\\~st~2"),
+                (
+                    "\"quick fox\"~5 -title:\"a b\"～1 \"c d\"~0 \"e f\"~",
+                    "\"quick fox\"~5 -title:\"a b\"~1 \"c d\" \"e f\"",
+                ),
+                ("price:＞＝100 -stars:<3", "price:>=100 -stars:<3"),
+                (
+                    "-title:(rust OR go) -title:(java)",
+                    "-title:(rust OR go) -title:java",
+                ),
+                (
+                    "price:[10　TO　100] -date:{ 2020-01-01 TO * ]",
+                    "price:[10 TO 100] -date:{2020-01-01 TO *]",
+                ),
+                (
+                    "\\(a\\) \\-b c\\:d \\OR \"e \\\" f\" ti\\ tle:(g*)",
+                    "\\(a\\) \\-b c\\:d \\OR \"e \\\" f\" ti\\ tle:g*",
+                ),
+                (
+                    "_exists_:title -_exists_:a\\:b _exists_:",
+                    "_exists_:title -_exists_:a\\:b _exists_\\:",
+                ),
+                (
+                    "/a b/ -title:/c\\/d/ log:(/e/ OR f) x/y/",
+                    "/a b/ -title:/c\\/d/ log:(/e/ OR f) x\\/y\\/",
+                ),
+                (
+                    "a ＮＥＡＲ／３ b ONEAR/1 c near/2 (d OR e) NEAR/1 -f",
+                    "(((a NEAR/3 b) ONEAR/1 c) NEAR/2 (d OR e)) NEAR/1 -f",
+                ),
+                ("x OR (a b) NEAR/5 c", "x OR (a b) NEAR/5 c"),
+                (
+                    "rust^2 \"a b\"~1^0.5 -(c OR d)^3 title:(e f)^2 ru*^2",
+                    "rust^2 \"a b\"~1^0.5 -(c OR d)^3 title:(e f)^2 ru*^2",
+                ),
+                (
+                    "title:\"g\"^2 body:h^2 (price:>1)^2 (-i)^2 j^NaN k\\^2",
+                    "title:\"g\"^2 body:h^2 (price:>1)^2 (-i)^2 j\\^NaN k\\^2",
+                ),
+                (
+                    "price:[a\\]b TO \\*] ti\\ tle:>\\=3 \\ a a\\:b:c",
+                    "price:[a\\]b TO \\*] ti\\ tle:>\\=3 \\ a a\\:b:c",
+                ),
+            ]
+            .into_iter()
+            .for_each(|(query, expected)| {
+                [Approach::Regex, Approach::Bnf]
+                    .into_iter()
+                    .for_each(|approach| {
+                        let condition = parse_query_to_condition_with(query, approach).unwrap();
+                        assert_eq!(
+                            condition.to_query_string(),
+                            expected,
+                            "{:?} {}",
+                            approach,
+                            query
+                        );
+                        assert_eq!(condition.to_string(), expected, "{:?} {}", approach, query);
+                        assert_eq!(
+                            parse_query_to_condition_with(expected, approach).unwrap(),
+                            condition,
+                            "{:?} {}",
+                            approach,
+                            query
+                        );
+                    })
+            })
+        }
+
+        #[test]
+        fn test_to_query_string_is_parsed_to_same_condition() {
+            let conditions = vec![
+                Condition::Not(Box::new(Condition::Not(Box::new(Condition::Keyword(
+                    "a".into(),
+                ))))),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("a".into()),
+                                Condition::Targeted(
+                                    "title^2".into(),
+                                    Box::new(Condition::Operator(
+                                        Operator::Or,
+                                        vec![
+                                            Condition::Keyword("b".into()),
+                                            Condition::Not(Box::new(Condition::PhraseKeyword(
+                                                "c d".into(),
+                                            ))),
+                                        ],
+                                    )),
+                                ),
+                            ],
+                        ),
+                        Condition::Not(Box::new(Condition::Targeted(
+                            "body".into(),
+                            Box::new(Condition::PhraseKeyword("e".into())),
+                        ))),
+                        Condition::Operator(Operator::Or, vec![Condition::None]),
+                    ],
+                ),
+                Condition::Not(Box::new(Condition::Targeted(
+                    "title".into(),
+                    Box::new(Condition::Operator(
+                        Operator::Or,
+                        vec![
+                            Condition::Keyword("a".into()),
+                            Condition::PhraseKeyword("b c".into()),
+                        ],
+                    )),
+                ))),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Boosted {
+                            condition: Box::new(Condition::Boosted {
+                                condition: Box::new(Condition::Keyword("a".into())),
+                                boost: Boost::new(2.0).unwrap(),
+                            }),
+                            boost: Boost::new(0.5).unwrap(),
+                        },
+                        Condition::Boosted {
+                            condition: Box::new(Condition::Exists {
+                                target: "title".into(),
+                            }),
+                            boost: Boost::new(3.0).unwrap(),
+                        },
+                        Condition::Targeted(
+                            "body".into(),
+                            Box::new(Condition::Boosted {
+                                condition: Box::new(Condition::Regex("b^c".into())),
+                                boost: Boost::new(2.0).unwrap(),
+                            }),
+                        ),
+                    ],
+                ),
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Range {
+                            target: "ti tle".into(),
+                            lower: Some("a]b".into()),
+                            upper: Some("*".into()),
+                            inclusive_lower: true,
+                            inclusive_upper: false,
+                        },
+                        Condition::Comparison {
+                            target: "a:b".into(),
+                            comparison: Comparison::GreaterThan,
+                            value: "=3".into(),
+                        },
+                        Condition::Keyword("-a:b".into()),
+                        Condition::Keyword("!:".into()),
+                        Condition::Keyword(" a ".into()),
+                        Condition::Targeted("a:b".into(), Box::new(Condition::Keyword("c".into()))),
+                        Condition::Keyword("ＮＥＡＲ／２".into()),
+                        Condition::Keyword("NEAR/2".into()),
+                        Condition::Targeted(
+                            "_exists_".into(),
+                            Box::new(Condition::Keyword("f".into())),
+                        ),
+                        Condition::Comparison {
+                            target: "_exists_".into(),
+                            comparison: Comparison::LessThan,
+                            value: "3".into(),
+                        },
+                    ],
+                ),
+            ];
+            conditions.into_iter().for_each(|condition| {
+                let query = condition.to_query_string();
+                [Approach::Regex, Approach::Bnf]
+                    .into_iter()
+                    .for_each(|approach| {
+                        assert_eq!(
+                            parse_query_to_condition_with(&query, approach).unwrap(),
+                            condition.clone().simplify(),
+                            "{:?} {}",
+                            approach,
+                            query
+                        )
+                    })
+            })
+        }
+
+        #[test]
+        fn test_to_query_string_with_exists_alias() {
+            let has = |condition: Condition| Condition::Targeted("has".into(), Box::new(condition));
+            let condition = Condition::Operator(
+                Operator::And,
+                vec![
+                    has(Condition::Keyword("title".into())),
+                    Condition::Not(Box::new(has(Condition::Boosted {
+                        condition: Box::new(Condition::Keyword("body".into())),
+                        boost: Boost::new(2.0).unwrap(),
+                    }))),
+                    Condition::Exists {
+                        target: "author".into(),
+                    },
+                    Condition::Targeted(
+                        "_exists_".into(),
+                        Box::new(Condition::Keyword("f".into())),
+                    ),
+                ],
+            );
+            [Approach::Regex, Approach::Bnf]
+                .into_iter()
+                .for_each(|approach| {
+                    let parser = Parser::new()
+                        .with_approach(approach)
+                        .with_exists_alias("has");
+                    let query = parser.to_query_string(&condition);
+                    assert_eq!(
+                        query, "\\has:title -\\has:body^2 _exists_:author \\_exists_:f",
+                        "{:?}",
+                        approach
+                    );
+                    assert_eq!(parser.parse(&query).unwrap(), condition, "{:?}", approach);
+                    assert_eq!(
+                        parser.parse("has:title").unwrap(),
+                        Condition::Exists {
+                            target: "title".into()
+                        },
+                        "{:?}",
+                        approach
+                    );
+                })
+        }
+
+        #[test]
+        fn test_to_query_string_boolean() {
+            vec![
+                ("+a b -c", "+a b -c"),
+                ("(+a b) OR c -(d -e)", "(+a b) OR (c -(d -e))"),
+                ("+(a OR b) +title:(c d) x", "+(a OR b) +title:(c d) x"),
+            ]
+            .into_iter()
+            .for_each(|(query, expected)| {
+                let parser = Parser::new().with_default_operator(DefaultOperator::Or);
+                let condition = parser.parse(query).unwrap();
+                assert_eq!(condition.to_query_string(), expected, "{}", query);
+                assert_eq!(
+                    parser.parse(&condition.to_query_string()).unwrap(),
+                    condition,
+                    "{}",
+                    query
+                );
+            })
+        }
+    }
+}
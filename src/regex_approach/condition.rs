@@ -1,11 +1,12 @@
+use crate::boost::parse_weight;
 use crate::regex_approach::query::Query;
 use crate::Condition::Not;
-use crate::{Condition, ConditionOnTarget, Target};
+use crate::{Condition, ConditionOnTarget, Occur, Operator, Target};
 
 impl Condition {
     pub(crate) fn simplify(self) -> Self {
         match self {
-            Not(condition) => match condition.simplify() {
+            Not(condition) => match condition.simplify().ungroup() {
                 Condition::None => Condition::None,
                 Not(condition) => condition.as_ref().clone(),
                 condition => Not(Box::new(condition)),
@@ -15,16 +16,14 @@ impl Condition {
                     .into_iter()
                     .filter_map(|condition| match condition.simplify() {
                         Condition::None => Option::None,
+                        condition if operator != Operator::And => Option::Some(condition.ungroup()),
                         condition => Option::Some(condition),
                     })
                     .collect::<Vec<Condition>>();
                 match conditions.len() {
                     0 => Condition::None,
                     // when only one child, remove self's operator layer
-                    1 => conditions
-                        .get(0)
-                        .map(|condition| condition.clone())
-                        .unwrap_or(Condition::None),
+                    1 => conditions.first().cloned().unwrap_or(Condition::None),
                     // when child is also a operator, and child's operator is equal to self's operator, remove child's operator layer
                     _ => Condition::Operator(
                         operator.clone(),
@@ -58,53 +57,200 @@ impl Condition {
                     Condition::None
                 }
             }
+            Condition::Prefix(k) => {
+                if Query::new(k.clone()).is_not_blank() {
+                    Condition::Prefix(k)
+                } else {
+                    Condition::None
+                }
+            }
+            Condition::Wildcard(k) => {
+                if Query::new(k.clone()).is_not_blank() {
+                    Condition::Wildcard(k)
+                } else {
+                    Condition::None
+                }
+            }
+            Condition::Proximity { phrase, slop } => {
+                if Query::new(phrase.clone()).is_not_blank() {
+                    Condition::Proximity { phrase, slop }
+                } else {
+                    Condition::None
+                }
+            }
+            Condition::Fuzzy { term, max_edits } => {
+                if Query::new(term.clone()).is_not_blank() {
+                    Condition::Fuzzy { term, max_edits }
+                } else {
+                    Condition::None
+                }
+            }
+            Condition::Targeted(target, condition) => match condition.simplify().ungroup() {
+                Condition::None => Condition::None,
+                condition => Condition::Targeted(target, Box::new(condition)),
+            },
+            Condition::Boosted { condition, boost } => match condition.simplify().ungroup() {
+                Condition::None => Condition::None,
+                condition => Condition::Boosted {
+                    condition: Box::new(condition),
+                    boost,
+                },
+            },
+            // a clause alone is kept, because `+A` is a must clause until the default operator is applied
+            Condition::Boolean(clauses) => {
+                let mut clauses = clauses
+                    .into_iter()
+                    .filter_map(|(occur, condition)| match condition.simplify() {
+                        Condition::None => Option::None,
+                        condition => Option::Some((occur, condition)),
+                    })
+                    .collect::<Vec<(Occur, Condition)>>();
+                match clauses.as_slice() {
+                    [] => Condition::None,
+                    [(Occur::Should, condition)] if !condition.is_kept_group() => {
+                        clauses.remove(0).1
+                    }
+                    _ => Condition::Boolean(clauses),
+                }
+            }
             _ => self,
         }
     }
 
     pub(crate) fn identify_target(self) -> ConditionOnTarget {
+        self.identify_target_on(None)
+    }
+
+    // the target of a targeted bracket or keyword is inherited by all its children, unless a child has its own target
+    fn identify_target_on(self, inherited_target: Option<Target>) -> ConditionOnTarget {
         match self {
             Condition::None => ConditionOnTarget::None,
-            Condition::Keyword(keyword) => {
-                let target_keyword = keyword
-                    .split(&[':', '：'])
-                    .filter(|it| it.is_empty() == false)
-                    .collect::<Vec<&str>>();
-                match target_keyword.len() {
-                    2 => ConditionOnTarget::Keyword {
-                        condition: target_keyword.get(1).unwrap().to_string(),
-                        target: Some(parse_target(target_keyword.get(0).unwrap().to_string())),
-                    },
-                    _ => ConditionOnTarget::Keyword {
-                        condition: keyword,
-                        target: None,
-                    },
+            Condition::Keyword(keyword) => ConditionOnTarget::Keyword {
+                condition: keyword,
+                target: inherited_target,
+            },
+            Condition::Prefix(prefix) => ConditionOnTarget::Prefix {
+                condition: prefix,
+                target: inherited_target,
+            },
+            Condition::Wildcard(wildcard) => ConditionOnTarget::Wildcard {
+                condition: wildcard,
+                target: inherited_target,
+            },
+            Condition::Fuzzy { term, max_edits } => ConditionOnTarget::Fuzzy {
+                condition: term,
+                max_edits,
+                target: inherited_target,
+            },
+            Condition::PhraseKeyword(phrase_keyword) => ConditionOnTarget::PhraseKeyword {
+                condition: phrase_keyword,
+                target: inherited_target,
+            },
+            Condition::Proximity { phrase, slop } => ConditionOnTarget::Proximity {
+                condition: phrase,
+                slop,
+                target: inherited_target,
+            },
+            Condition::Regex(pattern) => ConditionOnTarget::Regex {
+                condition: pattern,
+                target: inherited_target,
+            },
+            // the range has its own target always
+            Condition::Range {
+                target,
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+            } => ConditionOnTarget::Range {
+                lower,
+                upper,
+                inclusive_lower,
+                inclusive_upper,
+                target: Some(parse_target(target)),
+            },
+            Condition::Comparison {
+                target,
+                comparison,
+                value,
+            } => ConditionOnTarget::Comparison {
+                comparison,
+                value,
+                target: Some(parse_target(target)),
+            },
+            Condition::Exists { target } => ConditionOnTarget::Exists {
+                target: Some(parse_target(target)),
+            },
+            Not(condition) => {
+                let condition = condition.identify_target_on(inherited_target);
+                ConditionOnTarget::Not {
+                    target: condition.target().cloned(),
+                    condition: Box::new(condition),
                 }
             }
-            Condition::PhraseKeyword(phrase_keyword) => {
-                todo!()
-            }
-            Not(condition) => {
-                todo!()
+            Condition::Operator(operator, conditions) => ConditionOnTarget::Operator {
+                operator,
+                conditions: conditions
+                    .into_iter()
+                    .map(|condition| condition.identify_target_on(inherited_target.clone()))
+                    .collect(),
+                target: inherited_target,
+            },
+            Condition::Targeted(target, condition) => {
+                condition.identify_target_on(Some(parse_target(target)))
             }
-            Condition::Operator(operator, condition) => {
-                todo!()
+            Condition::Boosted { condition, boost } => {
+                let condition = condition.identify_target_on(inherited_target);
+                ConditionOnTarget::Boosted {
+                    target: condition.target().cloned(),
+                    condition: Box::new(condition),
+                    boost,
+                }
             }
+            Condition::Boolean(clauses) => ConditionOnTarget::Boolean {
+                clauses: clauses
+                    .into_iter()
+                    .map(|(occur, condition)| {
+                        (
+                            occur,
+                            condition.identify_target_on(inherited_target.clone()),
+                        )
+                    })
+                    .collect(),
+                target: inherited_target,
+            },
+        }
+    }
+}
+
+impl ConditionOnTarget {
+    pub fn target(&self) -> Option<&Target> {
+        match self {
+            ConditionOnTarget::None => None,
+            ConditionOnTarget::Keyword { target, .. }
+            | ConditionOnTarget::PhraseKeyword { target, .. }
+            | ConditionOnTarget::Prefix { target, .. }
+            | ConditionOnTarget::Wildcard { target, .. }
+            | ConditionOnTarget::Fuzzy { target, .. }
+            | ConditionOnTarget::Proximity { target, .. }
+            | ConditionOnTarget::Regex { target, .. }
+            | ConditionOnTarget::Range { target, .. }
+            | ConditionOnTarget::Comparison { target, .. }
+            | ConditionOnTarget::Exists { target }
+            | ConditionOnTarget::Not { target, .. }
+            | ConditionOnTarget::Operator { target, .. }
+            | ConditionOnTarget::Boolean { target, .. }
+            | ConditionOnTarget::Boosted { target, .. } => target.as_ref(),
         }
     }
 }
 
 fn parse_target(target_str: String) -> Target {
-    let target_weight = target_str.split("^").collect::<Vec<&str>>();
+    let target_weight = target_str.split('^').collect::<Vec<&str>>();
     match target_weight.len() {
         2 => Target {
-            name: target_weight.get(0).unwrap().to_string(),
-            weight: target_weight
-                .get(1)
-                .unwrap()
-                .to_string()
-                .parse::<f32>()
-                .map_or(None, |it| Some(it)),
+            name: target_weight[0].to_string(),
+            weight: parse_weight(target_weight[1]),
         },
         _ => Target {
             name: target_str,
@@ -119,13 +265,33 @@ mod tests {
 
     mod test_condition_simplify {
         use super::*;
-        use crate::Operator;
+        use crate::{Boost, Operator};
 
         #[test]
         fn test_simplify_none() {
             assert_eq!(Condition::None.simplify(), Condition::None)
         }
 
+        #[test]
+        fn test_simplify_boosted() {
+            let boosted = |condition: Condition| Condition::Boosted {
+                condition: Box::new(condition),
+                boost: Boost::new(2.0).unwrap(),
+            };
+            assert_eq!(
+                boosted(Condition::group(Condition::Operator(
+                    Operator::And,
+                    vec![Condition::Keyword("keyword".into()), Condition::None]
+                )))
+                .simplify(),
+                boosted(Condition::Keyword("keyword".into()))
+            );
+            assert_eq!(
+                boosted(Condition::Keyword(" ".into())).simplify(),
+                Condition::None
+            )
+        }
+
         #[test]
         fn test_simplify_keyword() {
             assert_eq!(
@@ -276,6 +442,33 @@ mod tests {
             )
         }
 
+        #[test]
+        fn test_simplify_targeted_keyword() {
+            assert_eq!(
+                Condition::Targeted(
+                    "target".into(),
+                    Box::new(Condition::Operator(
+                        Operator::And,
+                        vec![Condition::Keyword("keyword".into())]
+                    ))
+                )
+                .simplify(),
+                Condition::Targeted(
+                    "target".into(),
+                    Box::new(Condition::Keyword("keyword".into()))
+                )
+            )
+        }
+
+        #[test]
+        fn test_simplify_targeted_none() {
+            assert_eq!(
+                Condition::Targeted("target".into(), Box::new(Condition::Keyword("".into())))
+                    .simplify(),
+                Condition::None
+            )
+        }
+
         #[test]
         fn test_simplify_operator_and_empty() {
             assert_eq!(
@@ -625,7 +818,8 @@ mod tests {
     }
 
     mod test_identify_target {
-        use crate::{Condition, ConditionOnTarget, Target};
+        use crate::term::term_condition;
+        use crate::{Boost, Condition, ConditionOnTarget, Operator, Target};
 
         #[test]
         fn test_identify_target_on_none_condition() {
@@ -646,7 +840,11 @@ mod tests {
         #[test]
         fn test_identify_target_on_keyword_condition() {
             assert_eq!(
-                Condition::Keyword("hoge:fuga".to_string()).identify_target(),
+                Condition::Targeted(
+                    "hoge".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )
+                .identify_target(),
                 ConditionOnTarget::Keyword {
                     condition: "fuga".to_string(),
                     target: Some(Target {
@@ -657,10 +855,29 @@ mod tests {
             )
         }
 
+        // since 0.2.0 the target is parsed to `Condition::Targeted`, so the colon in a keyword is a part of it
+        #[test]
+        fn test_identify_target_on_keyword_condition_with_colon() {
+            assert_eq!(
+                crate::parse_query_to_condition("hoge:fuga").unwrap(),
+                Condition::Targeted(
+                    "hoge".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )
+            );
+            assert_eq!(
+                Condition::Keyword("hoge:fuga".to_string()).identify_target(),
+                ConditionOnTarget::Keyword {
+                    condition: "hoge:fuga".to_string(),
+                    target: None
+                }
+            )
+        }
+
         #[test]
         fn test_identify_target_on_keyword_condition_full_width() {
             assert_eq!(
-                Condition::Keyword("hoge：fuga".to_string()).identify_target(),
+                term_condition("hoge：fuga".into(), None).identify_target(),
                 ConditionOnTarget::Keyword {
                     condition: "fuga".to_string(),
                     target: Some(Target {
@@ -674,7 +891,11 @@ mod tests {
         #[test]
         fn test_identify_target_with_i32_weight_on_keyword_condition() {
             assert_eq!(
-                Condition::Keyword("hoge^2:fuga".to_string()).identify_target(),
+                Condition::Targeted(
+                    "hoge^2".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )
+                .identify_target(),
                 ConditionOnTarget::Keyword {
                     condition: "fuga".to_string(),
                     target: Some(Target {
@@ -688,7 +909,11 @@ mod tests {
         #[test]
         fn test_identify_target_with_f32_weight_on_keyword_condition() {
             assert_eq!(
-                Condition::Keyword("hoge^0.2:fuga".to_string()).identify_target(),
+                Condition::Targeted(
+                    "hoge^0.2".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )
+                .identify_target(),
                 ConditionOnTarget::Keyword {
                     condition: "fuga".to_string(),
                     target: Some(Target {
@@ -702,7 +927,11 @@ mod tests {
         #[test]
         fn test_identify_target_with_nan_weight_on_keyword_condition() {
             assert_eq!(
-                Condition::Keyword("hoge^2a:fuga".to_string()).identify_target(),
+                Condition::Targeted(
+                    "hoge^2a".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )
+                .identify_target(),
                 ConditionOnTarget::Keyword {
                     condition: "fuga".to_string(),
                     target: Some(Target {
@@ -713,6 +942,56 @@ mod tests {
             )
         }
 
+        #[test]
+        fn test_identify_target_with_infinite_weight_on_keyword_condition() {
+            ["hoge^NaN", "hoge^inf"].into_iter().for_each(|target| {
+                assert_eq!(
+                    Condition::Targeted(
+                        target.to_string(),
+                        Box::new(Condition::Keyword("fuga".to_string()))
+                    )
+                    .identify_target(),
+                    ConditionOnTarget::Keyword {
+                        condition: "fuga".to_string(),
+                        target: Some(Target {
+                            name: "hoge".to_string(),
+                            weight: None
+                        })
+                    },
+                    "{}",
+                    target
+                )
+            })
+        }
+
+        #[test]
+        fn test_identify_target_on_boosted_condition() {
+            assert_eq!(
+                Condition::Targeted(
+                    "title".to_string(),
+                    Box::new(Condition::Boosted {
+                        condition: Box::new(Condition::Keyword("fuga".to_string())),
+                        boost: Boost::new(0.5).unwrap()
+                    })
+                )
+                .identify_target(),
+                ConditionOnTarget::Boosted {
+                    condition: Box::new(ConditionOnTarget::Keyword {
+                        condition: "fuga".to_string(),
+                        target: Some(Target {
+                            name: "title".to_string(),
+                            weight: None
+                        })
+                    }),
+                    boost: Boost::new(0.5).unwrap(),
+                    target: Some(Target {
+                        name: "title".to_string(),
+                        weight: None
+                    })
+                }
+            )
+        }
+
         #[test]
         fn test_identify_target_on_keyword_condition_invalid() {
             assert_eq!(
@@ -737,5 +1016,141 @@ mod tests {
                 }
             )
         }
+
+        #[test]
+        fn test_identify_target_on_phrase_keyword_condition() {
+            assert_eq!(
+                Condition::PhraseKeyword("hoge:fuga".to_string()).identify_target(),
+                ConditionOnTarget::PhraseKeyword {
+                    condition: "hoge:fuga".to_string(),
+                    target: None
+                }
+            )
+        }
+
+        #[test]
+        fn test_identify_target_on_targeted_phrase_keyword_condition() {
+            assert_eq!(
+                Condition::Targeted(
+                    "hoge^2".to_string(),
+                    Box::new(Condition::PhraseKeyword("fuga pico".to_string()))
+                )
+                .identify_target(),
+                ConditionOnTarget::PhraseKeyword {
+                    condition: "fuga pico".to_string(),
+                    target: Some(Target {
+                        name: "hoge".to_string(),
+                        weight: Some(2.0)
+                    })
+                }
+            )
+        }
+
+        #[test]
+        fn test_identify_target_on_not_condition() {
+            assert_eq!(
+                Condition::Not(Box::new(Condition::Targeted(
+                    "hoge".to_string(),
+                    Box::new(Condition::Keyword("fuga".to_string()))
+                )))
+                .identify_target(),
+                ConditionOnTarget::Not {
+                    condition: Box::new(ConditionOnTarget::Keyword {
+                        condition: "fuga".to_string(),
+                        target: Some(Target {
+                            name: "hoge".to_string(),
+                            weight: None
+                        })
+                    }),
+                    target: Some(Target {
+                        name: "hoge".to_string(),
+                        weight: None
+                    })
+                }
+            )
+        }
+
+        #[test]
+        fn test_identify_target_on_operator_condition() {
+            assert_eq!(
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Targeted(
+                            "hoge".to_string(),
+                            Box::new(Condition::Keyword("fuga".to_string()))
+                        ),
+                        Condition::PhraseKeyword("pico".to_string())
+                    ]
+                )
+                .identify_target(),
+                ConditionOnTarget::Operator {
+                    operator: Operator::Or,
+                    conditions: vec![
+                        ConditionOnTarget::Keyword {
+                            condition: "fuga".to_string(),
+                            target: Some(Target {
+                                name: "hoge".to_string(),
+                                weight: None
+                            })
+                        },
+                        ConditionOnTarget::PhraseKeyword {
+                            condition: "pico".to_string(),
+                            target: None
+                        }
+                    ],
+                    target: None
+                }
+            )
+        }
+
+        #[test]
+        fn test_identify_target_on_targeted_operator_condition() {
+            let hoge = Some(Target {
+                name: "hoge".to_string(),
+                weight: None,
+            });
+            assert_eq!(
+                Condition::Targeted(
+                    "hoge".to_string(),
+                    Box::new(Condition::Operator(
+                        Operator::And,
+                        vec![
+                            Condition::Keyword("fuga".to_string()),
+                            Condition::Not(Box::new(Condition::PhraseKeyword("pico".to_string()))),
+                            Condition::Targeted(
+                                "piyo".to_string(),
+                                Box::new(Condition::Keyword("puyo".to_string()))
+                            ),
+                        ]
+                    ))
+                )
+                .identify_target(),
+                ConditionOnTarget::Operator {
+                    operator: Operator::And,
+                    conditions: vec![
+                        ConditionOnTarget::Keyword {
+                            condition: "fuga".to_string(),
+                            target: hoge.clone()
+                        },
+                        ConditionOnTarget::Not {
+                            condition: Box::new(ConditionOnTarget::PhraseKeyword {
+                                condition: "pico".to_string(),
+                                target: hoge.clone()
+                            }),
+                            target: hoge.clone()
+                        },
+                        ConditionOnTarget::Keyword {
+                            condition: "puyo".to_string(),
+                            target: Some(Target {
+                                name: "piyo".to_string(),
+                                weight: None
+                            })
+                        },
+                    ],
+                    target: hoge
+                }
+            )
+        }
     }
 }
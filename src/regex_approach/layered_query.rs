@@ -1,37 +1,57 @@
+use crate::escape::{unescape, Escapes};
 use crate::regex_approach::query::Query;
 use crate::regex_approach::{regex_match_not_blank_query, regex_match_number};
-use crate::{Condition, Operator};
+use crate::{Condition, Occur};
 use eyre::Result;
 use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+static REGEX_BRACKET: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(([^()]*)\)").unwrap());
+static REGEX_LAYERED_BY_BRACKET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([^（）]*)（(\d+)）").unwrap());
+static REGEX_TARGET_BEFORE_BRACKET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(^|\s)(?P<sign>[-+])?(?P<target>[^\s\-:：][^\s:：]*)[:：]$").unwrap()
+});
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum LayeredQuery {
     Query(Query),
     Bracket(LayeredQueries),
     NegativeBracket(LayeredQueries),
+    TargetBracket(String, LayeredQueries),
+    NegativeTargetBracket(String, LayeredQueries),
+    RequiredBracket(LayeredQueries),
+    RequiredTargetBracket(String, LayeredQueries),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct LayeredQueries(Vec<LayeredQuery>);
 
 impl LayeredQueries {
-    pub(crate) fn parse(query: Query) -> Result<LayeredQueries> {
+    pub(crate) fn parse(
+        query: Query, symbolic_operators: bool, escapes: &Escapes,
+    ) -> Result<LayeredQueries> {
         let (query, negative_phrase_keywords, phrase_keywords) = query
             .normalize_double_quotation()
             .extract_phrase_keywords()?;
-        let query = query.normalize_symbols_except_double_quotation();
+        let query = query
+            .normalize_symbols_except_double_quotation()
+            .normalize_symbolic_operators(symbolic_operators)
+            .normalize_negation()
+            .join_ranges();
         let mut bracket_queries = Vec::<Query>::new();
         let all_brackets_picked_query = Self::pick_layer_by_bracket(query, &mut bracket_queries)?;
-        Ok(Self::combine_layered_query(
+        Self::combine_layered_query(
             all_brackets_picked_query,
             &bracket_queries,
             &negative_phrase_keywords,
             &phrase_keywords,
-        )?)
+            escapes,
+        )
     }
 
     fn pick_layer_by_bracket(query: Query, bracket_queries: &mut Vec<Query>) -> Result<Query> {
-        let regex_bracket = Regex::new(r"\(([^()]*)\)")?;
+        let regex_bracket = &*REGEX_BRACKET;
         let innermost_bracket_removed_query = Query::new(
             regex_bracket
                 .replace_all(query.value_ref(), |captures: &Captures| {
@@ -52,27 +72,38 @@ impl LayeredQueries {
     }
 
     fn combine_layered_query(
-        query: Query, bracket_queries: &Vec<Query>, negative_phrase_keywords: &Vec<Query>,
-        phrase_keywords: &Vec<Query>,
+        query: Query, bracket_queries: &[Query], negative_phrase_keywords: &[Query],
+        phrase_keywords: &[Query], escapes: &Escapes,
     ) -> Result<LayeredQueries> {
-        let regex_layered_by_bracket = Regex::new(r"([^（）]*)（(\d+)）")?;
+        let regex_layered_by_bracket = &*REGEX_LAYERED_BY_BRACKET;
+        let regex_target_before_bracket = &*REGEX_TARGET_BEFORE_BRACKET;
         let mut layered_queries = Vec::<LayeredQuery>::new();
         let the_last_query_after_all_brackets = regex_layered_by_bracket
             .replace_all(query.value_ref(), |captures: &Captures| {
-                let mut is_negative_bracket = false;
-                regex_match_not_blank_query(captures.get(1)).map(|mut q| {
-                    if q.value_ref().ends_with("-") {
-                        is_negative_bracket = true;
+                // `-` or `+` before the bracket
+                let mut bracket_sign = Option::<char>::None;
+                let mut bracket_target = Option::<String>::None;
+                if let Some(mut q) = regex_match_not_blank_query(captures.get(1)) {
+                    if let Some(target) = regex_target_before_bracket.captures(q.value_ref()) {
+                        bracket_target = target
+                            .name("target")
+                            .map(|t| unescape(&escapes.restore(t.as_str()), &[]));
+                        bracket_sign = target.name("sign").and_then(|s| s.as_str().chars().next());
+                        q = Query::new(String::from(
+                            &q.value_ref()[0..target.get(0).unwrap().start()],
+                        ))
+                    } else if q.value_ref().ends_with(['-', '+']) {
+                        bracket_sign = q.value_ref().chars().last();
                         q = Query::new(String::from(&q.value_ref()[0..q.value_ref().len() - 1]))
                     }
-                    let _ = q
-                        .combine_phrase_keywords(negative_phrase_keywords, phrase_keywords)
-                        .map(|q| {
-                            if q.is_not_blank() {
-                                layered_queries.push(LayeredQuery::Query(q))
-                            }
-                        });
-                });
+                    if let Ok(q) =
+                        q.combine_phrase_keywords(negative_phrase_keywords, phrase_keywords)
+                    {
+                        if q.is_not_blank() {
+                            layered_queries.push(LayeredQuery::Query(q))
+                        }
+                    }
+                }
                 regex_match_number(captures.get(2), |i| {
                     bracket_queries.get(i - 1).map(|q| {
                         Self::combine_layered_query(
@@ -80,12 +111,22 @@ impl LayeredQueries {
                             bracket_queries,
                             negative_phrase_keywords,
                             phrase_keywords,
+                            escapes,
                         )
                         .map(|lqs| {
-                            layered_queries.push(if is_negative_bracket {
-                                LayeredQuery::NegativeBracket(lqs)
-                            } else {
-                                LayeredQuery::Bracket(lqs)
+                            layered_queries.push(match (bracket_sign, &bracket_target) {
+                                (Some('-'), Some(target)) => {
+                                    LayeredQuery::NegativeTargetBracket(target.clone(), lqs)
+                                }
+                                (Some(_), Some(target)) => {
+                                    LayeredQuery::RequiredTargetBracket(target.clone(), lqs)
+                                }
+                                (None, Some(target)) => {
+                                    LayeredQuery::TargetBracket(target.clone(), lqs)
+                                }
+                                (Some('-'), None) => LayeredQuery::NegativeBracket(lqs),
+                                (Some(_), None) => LayeredQuery::RequiredBracket(lqs),
+                                (None, None) => LayeredQuery::Bracket(lqs),
                             })
                         })
                     })
@@ -101,73 +142,101 @@ impl LayeredQueries {
         Ok(Self(layered_queries))
     }
 
-    pub(crate) fn to_condition(self) -> Result<Condition> {
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn to_condition(
+        self, escapes: &Escapes, exists_alias: Option<&str>,
+    ) -> Result<Condition> {
         let mut query_string = String::new();
         let mut conditions = Vec::<Condition>::new();
 
-        for layered_query in self.0 {
+        let mut layered_queries = self.0.into_iter().peekable();
+        while let Some(layered_query) = layered_queries.next() {
+            // `(A B)^2`, the boost just after the brackets is taken from the query after them
+            let boost = match (&layered_query, layered_queries.peek_mut()) {
+                (LayeredQuery::Query(_), _) => None,
+                (_, Some(LayeredQuery::Query(next))) => next.take_boost(),
+                _ => None,
+            };
+            let boosted = |condition: Condition| match boost {
+                Some(boost) => condition.boosted(boost),
+                None => condition,
+            };
             match layered_query {
-                LayeredQuery::Query(query) => {
-                    let (is_start_with_or, condition, is_end_with_or) = query.to_condition()?;
-                    query_string.push_str(
-                        format!(
-                            " {} {} {} ",
-                            if is_start_with_or { "or" } else { "and" },
-                            conditions.len(),
-                            if is_end_with_or { "or" } else { "and" }
-                        )
-                        .as_str(),
-                    );
-                    conditions.push(condition);
-                }
+                LayeredQuery::Query(query) => query_string.push_str(&query.index_keywords(
+                    &mut conditions,
+                    escapes,
+                    exists_alias,
+                )?),
                 LayeredQuery::Bracket(layered_queries) => {
-                    let condition = layered_queries.to_condition()?;
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
                     query_string.push_str(format!(" {} ", conditions.len()).as_str());
-                    conditions.push(condition);
+                    conditions.push(boosted(Condition::group(condition)));
                 }
                 LayeredQuery::NegativeBracket(layered_queries) => {
-                    let condition = layered_queries.to_condition()?;
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
+                    query_string.push_str(format!(" {} ", conditions.len()).as_str());
+                    conditions.push(Condition::Not(Box::new(boosted(condition))));
+                }
+                LayeredQuery::TargetBracket(target, layered_queries) => {
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
                     query_string.push_str(format!(" {} ", conditions.len()).as_str());
-                    conditions.push(Condition::Not(Box::new(condition)));
+                    conditions.push(boosted(Condition::Targeted(target, Box::new(condition))));
+                }
+                LayeredQuery::NegativeTargetBracket(target, layered_queries) => {
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
+                    query_string.push_str(format!(" {} ", conditions.len()).as_str());
+                    conditions.push(Condition::Not(Box::new(boosted(Condition::Targeted(
+                        target,
+                        Box::new(condition),
+                    )))));
+                }
+                LayeredQuery::RequiredBracket(layered_queries) => {
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
+                    query_string.push_str(format!(" {} ", conditions.len()).as_str());
+                    conditions.push(Condition::required(boosted(condition)));
+                }
+                LayeredQuery::RequiredTargetBracket(target, layered_queries) => {
+                    let condition = layered_queries.to_condition(escapes, exists_alias)?;
+                    query_string.push_str(format!(" {} ", conditions.len()).as_str());
+                    conditions.push(Condition::required(boosted(Condition::Targeted(
+                        target,
+                        Box::new(condition),
+                    ))));
                 }
             }
         }
 
         let query = Query::new(query_string);
-        let (_, condition, _) = query.to_condition()?;
-        let condition = match condition {
-            Condition::Keyword(index) => Self::get_condition(index, &conditions)?,
-            Condition::Operator(operator, layer1_conditions) => {
-                let mut real_layer1_conditions = Vec::<Condition>::new();
-                for condition in layer1_conditions {
-                    real_layer1_conditions.push(match condition {
-                        Condition::Keyword(index) => Self::get_condition(index, &conditions)?,
-                        Condition::Operator(Operator::And, layer2_conditions) => {
-                            let mut real_layer2_conditions = Vec::<Condition>::new();
-                            for condition in layer2_conditions {
-                                real_layer2_conditions.push(match condition {
-                                    Condition::Keyword(index) => {
-                                        Self::get_condition(index, &conditions)?
-                                    }
-                                    _ => Condition::None,
-                                })
-                            }
-                            Condition::Operator(Operator::And, real_layer2_conditions)
-                        }
-                        _ => Condition::None,
-                    })
+        let condition = query.to_condition(escapes, exists_alias)?;
+        Ok(Self::get_conditions(condition, &conditions)?.simplify())
+    }
+
+    // the indexes in the layer are replaced with the conditions, they are in `OR`, `AND` and `NEAR/n`
+    fn get_conditions(condition: Condition, conditions: &[Condition]) -> Result<Condition> {
+        Ok(match condition {
+            Condition::Keyword(index) => Self::get_condition(index, conditions)?,
+            Condition::Operator(operator, layer_conditions) => Condition::Operator(
+                operator,
+                layer_conditions
+                    .into_iter()
+                    .map(|condition| Self::get_conditions(condition, conditions))
+                    .collect::<Result<Vec<Condition>>>()?,
+            ),
+            // `A AND B`, the index is required
+            Condition::Boolean(clauses) => match clauses.into_iter().next() {
+                Some((Occur::Must, condition)) => {
+                    Self::get_conditions(condition, conditions)?.required_by_and()
                 }
-                Condition::Operator(operator, real_layer1_conditions)
-            }
+                _ => Condition::None,
+            },
             _ => Condition::None,
-        };
-        Ok(condition.simplify())
+        })
     }
 
-    fn get_condition(index: String, conditions: &Vec<Condition>) -> Result<Condition> {
+    fn get_condition(index: String, conditions: &[Condition]) -> Result<Condition> {
         Ok(conditions
             .get(index.parse::<usize>()?)
-            .map(|condition: &Condition| condition.clone())
+            .cloned()
             .unwrap_or(Condition::None))
     }
 }
@@ -175,6 +244,7 @@ impl LayeredQueries {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Operator;
 
     mod test_parse {
         use super::*;
@@ -186,7 +256,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![LayeredQuery::Query(Query::new(
                     " ＡＡＡ  \"１１１　ＣＣＣ\"  -ＤＤＤ or エエエ and ＦＦＦ  -\"あああ　いいい\" "
                         .into()
@@ -201,7 +271,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
@@ -219,7 +289,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::NegativeBracket(LayeredQueries(vec![LayeredQuery::Query(
@@ -237,7 +307,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
                         " ＡＡＡ  \"１１１　ＣＣＣ\" ".into()
@@ -260,7 +330,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
                         " ＡＡＡ  \"１１１　ＣＣＣ\" ".into()
@@ -283,7 +353,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![
@@ -307,7 +377,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ ".into())),
                     LayeredQuery::NegativeBracket(LayeredQueries(vec![
@@ -334,7 +404,7 @@ mod tests {
             let query =
                 Query::new("　ＡＡＡ　（”１１１　ＣＣＣ”　（-（　ＤＤＤ　エエエ　）　ＦＦＦ）　ＧＧＧ　（ＨＨＨ　-”あああ　いいい”　ううう））　”　ＪＪＪ　”　-（ＫＫＫ　（　）　ＬＬＬ）　　（ＭＭＭ）　２２２　".into());
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![
@@ -369,7 +439,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ   \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
@@ -387,7 +457,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  -ＤＤＤ or エエエ and  ＦＦＦ  -\"あああ　いいい\" "
                     .into()))])
             )
@@ -400,7 +470,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  -ＤＤＤ or エエエ  and ＦＦＦ  -\"あああ　いいい\" "
                     .into()))])
             )
@@ -413,7 +483,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
@@ -431,7 +501,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
@@ -449,7 +519,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::Query(Query::new(" ＡＡＡ  \"１１１　ＣＣＣ\"  ".into())),
                     LayeredQuery::Bracket(LayeredQueries(vec![LayeredQuery::Query(Query::new(
@@ -467,7 +537,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::NegativeBracket(LayeredQueries(vec![LayeredQuery::Query(
                         Query::new("Ａ１ or  \" Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) \" ".into())
@@ -487,7 +557,7 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query).unwrap(),
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
                 LayeredQueries(vec![
                     LayeredQuery::NegativeBracket(LayeredQueries(vec![LayeredQuery::Query(
                         Query::new(
@@ -502,6 +572,45 @@ mod tests {
                 ])
             )
         }
+
+        #[test]
+        fn test_parse_with_target_bracket() {
+            let query = Query::new("ＡＡＡ　title：（ＢＢＢ　or　ＣＣＣ）　-ＤＤＤ".into());
+            assert_eq!(
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
+                LayeredQueries(vec![
+                    LayeredQuery::Query(Query::new("ＡＡＡ".into())),
+                    LayeredQuery::TargetBracket(
+                        "title".into(),
+                        LayeredQueries(vec![LayeredQuery::Query(Query::new(
+                            "ＢＢＢ or ＣＣＣ".into()
+                        ))])
+                    ),
+                    LayeredQuery::Query(Query::new(" -ＤＤＤ".into()))
+                ])
+            )
+        }
+    }
+
+    mod test_parse_negative_target_bracket {
+        use super::*;
+
+        #[test]
+        fn test_parse_with_negative_target_bracket() {
+            let query = Query::new("ＡＡＡ　-title：（ＢＢＢ　or　ＣＣＣ）".into());
+            assert_eq!(
+                LayeredQueries::parse(query, true, &Escapes::default()).unwrap(),
+                LayeredQueries(vec![
+                    LayeredQuery::Query(Query::new("ＡＡＡ".into())),
+                    LayeredQuery::NegativeTargetBracket(
+                        "title".into(),
+                        LayeredQueries(vec![LayeredQuery::Query(Query::new(
+                            "ＢＢＢ or ＣＣＣ".into()
+                        ))])
+                    ),
+                ])
+            )
+        }
     }
 
     mod test_layered_queries_parse_to_condition {
@@ -511,9 +620,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_empty_string() {
             let query = Query::new("".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::None
             )
@@ -523,9 +632,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_blank_string() {
             let query = Query::new(" 　 ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::None
             )
@@ -535,9 +644,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_one_keyword() {
             let query = Query::new(" 検索 ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Keyword("検索".into())
             )
@@ -547,9 +656,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_one_phrase_keyword() {
             let query = Query::new(" \"検索\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::PhraseKeyword("検索".into())
             )
@@ -559,9 +668,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_one_negative_keyword() {
             let query = Query::new(" -検索 ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Not(Box::new(Condition::Keyword("検索".into())))
             )
@@ -571,9 +680,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_one_negative_phrase_keyword() {
             let query = Query::new(" -\"検索\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Not(Box::new(Condition::PhraseKeyword("検索".into())))
             )
@@ -583,9 +692,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_mutlti_keywords_concat_with_space() {
             let query = Query::new(" 検索１ -検索２ \"検索３\" -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::And,
@@ -603,16 +712,16 @@ mod tests {
         fn test_layered_queries_parse_to_condition_mutlti_keywords_concat_with_and() {
             let query = Query::new(" 検索１ and -検索２ and \"検索３\" and -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::And,
                     vec![
-                        Condition::Keyword("検索１".into()),
+                        Condition::required(Condition::Keyword("検索１".into())),
                         Condition::Not(Box::new(Condition::Keyword("検索２".into()))),
-                        Condition::PhraseKeyword("検索３".into()),
+                        Condition::required(Condition::PhraseKeyword("検索３".into())),
                         Condition::Not(Box::new(Condition::PhraseKeyword("検索４".into())))
                     ]
                 )
@@ -623,9 +732,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_mutlti_keywords_concat_with_or() {
             let query = Query::new(" 検索１ or -検索２ or \"検索３\" or -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -643,9 +752,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_mutlti_keywords_concat_with_space_or_and() {
             let query = Query::new(" 検索１ -検索２ or \"検索３\" and -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -660,7 +769,7 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::PhraseKeyword("検索３".into()),
+                                Condition::required(Condition::PhraseKeyword("検索３".into())),
                                 Condition::Not(Box::new(Condition::PhraseKeyword("検索４".into())))
                             ]
                         )
@@ -673,9 +782,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_or_in_brackets() {
             let query = Query::new(" 検索１ and (-検索２ or \"検索３\") or -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -683,8 +792,8 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Keyword("検索１".into()),
-                                Condition::Operator(
+                                Condition::required(Condition::Keyword("検索１".into())),
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::Not(Box::new(Condition::Keyword(
@@ -692,7 +801,7 @@ mod tests {
                                         ))),
                                         Condition::PhraseKeyword("検索３".into()),
                                     ]
-                                ),
+                                )),
                             ]
                         ),
                         Condition::Not(Box::new(Condition::PhraseKeyword("検索４".into())))
@@ -705,9 +814,9 @@ mod tests {
         fn test_layered_queries_parse_to_condition_or_in_negative_brackets() {
             let query = Query::new(" 検索１ and -(-検索２ or \"検索３\") or -\"検索４\" ".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -715,7 +824,7 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Keyword("検索１".into()),
+                                Condition::required(Condition::Keyword("検索１".into())),
                                 Condition::Not(Box::new(Condition::Operator(
                                     Operator::Or,
                                     vec![
@@ -740,27 +849,27 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::And,
                     vec![
-                        Condition::Operator(
+                        Condition::required(Condition::Operator(
                             Operator::Or,
                             vec![
                                 Condition::Keyword("検索１".into()),
                                 Condition::Not(Box::new(Condition::Keyword("検索２".into()))),
                             ]
-                        ),
-                        Condition::Operator(
+                        )),
+                        Condition::required(Condition::Operator(
                             Operator::Or,
                             vec![
                                 Condition::PhraseKeyword("検索３".into()),
                                 Condition::Not(Box::new(Condition::PhraseKeyword("検索４".into())))
                             ]
-                        ),
+                        )),
                         Condition::Operator(
                             Operator::Or,
                             vec![
@@ -780,9 +889,9 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -790,14 +899,14 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Keyword("word１".into()),
+                                Condition::required(Condition::Keyword("word１".into())),
                                 Condition::Not(Box::new(Condition::Keyword("word２".into()))),
                             ]
                         ),
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Operator(
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::PhraseKeyword("phrase word １".into()),
@@ -805,7 +914,7 @@ mod tests {
                                             "phrase word ２".into()
                                         )))
                                     ]
-                                ),
+                                )),
                                 Condition::Not(Box::new(Condition::Operator(
                                     Operator::Or,
                                     vec![
@@ -827,9 +936,9 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -837,14 +946,14 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Keyword("検索１".into()),
+                                Condition::required(Condition::Keyword("検索１".into())),
                                 Condition::Not(Box::new(Condition::Keyword("検索２".into()))),
                             ]
                         ),
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Operator(
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::PhraseKeyword(
@@ -854,14 +963,14 @@ mod tests {
                                             " ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) ".into()
                                         )))
                                     ]
-                                ),
-                                Condition::Operator(
+                                )),
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::PhraseKeyword(" 検索５ 検索６ ".into()),
                                         Condition::Keyword("検索７".into())
                                     ]
-                                )
+                                ))
                             ]
                         ),
                     ]
@@ -876,9 +985,9 @@ mod tests {
                     .into(),
             );
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -886,14 +995,14 @@ mod tests {
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Keyword("検索１".into()),
+                                Condition::required(Condition::Keyword("検索１".into())),
                                 Condition::Not(Box::new(Condition::Keyword("検索２".into()))),
                             ]
                         ),
                         Condition::Operator(
                             Operator::And,
                             vec![
-                                Condition::Operator(
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::PhraseKeyword("検索３".into()),
@@ -901,14 +1010,14 @@ mod tests {
                                             "検索４".into()
                                         )))
                                     ]
-                                ),
-                                Condition::Operator(
+                                )),
+                                Condition::required(Condition::Operator(
                                     Operator::Or,
                                     vec![
                                         Condition::PhraseKeyword(" 検索５ 検索６ ".into()),
                                         Condition::Keyword("検索７".into())
                                     ]
-                                )
+                                ))
                             ]
                         ),
                     ]
@@ -921,9 +1030,9 @@ mod tests {
             let query =
                 Query::new("　ＡＡＡ　（”１１１　ＣＣＣ”　or（-（　ＤＤＤ　or　エエエ　）and　ＦＦＦ）or　ＧＧＧ　（ＨＨＨ　or　-”あああ　いいい”　ううう））　”　ＪＪＪ　”　or　-（ＫＫＫ　and　（　）　or　ＬＬＬ）　　（ＭＭＭ）or　２２２　".into());
             assert_eq!(
-                LayeredQueries::parse(query)
+                LayeredQueries::parse(query, true, &Escapes::default())
                     .unwrap()
-                    .to_condition()
+                    .to_condition(&Escapes::default(), None)
                     .unwrap(),
                 Condition::Operator(
                     Operator::Or,
@@ -946,7 +1055,9 @@ mod tests {
                                                         Condition::Keyword("エエエ".into()),
                                                     ]
                                                 ))),
-                                                Condition::Keyword("ＦＦＦ".into()),
+                                                Condition::required(Condition::Keyword(
+                                                    "ＦＦＦ".into()
+                                                )),
                                             ]
                                         ),
                                         Condition::Operator(
@@ -995,5 +1106,33 @@ mod tests {
                 )
             )
         }
+
+        #[test]
+        fn test_layered_queries_parse_to_condition_target_bracket() {
+            let query = Query::new("Ａ１ or title:(Ａ２ Ａ３) or -\"ＮＰ１\"".into());
+            assert_eq!(
+                LayeredQueries::parse(query, true, &Escapes::default())
+                    .unwrap()
+                    .to_condition(&Escapes::default(), None)
+                    .unwrap(),
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("Ａ１".into()),
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::Operator(
+                                Operator::And,
+                                vec![
+                                    Condition::Keyword("Ａ２".into()),
+                                    Condition::Keyword("Ａ３".into()),
+                                ]
+                            ))
+                        ),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＮＰ１".into()))),
+                    ]
+                )
+            )
+        }
     }
 }
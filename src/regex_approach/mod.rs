@@ -15,7 +15,6 @@ pub(crate) fn regex_match_number<F: FnOnce(usize) -> Option<R>, R>(
     regex_match: Option<Match>, call_back: F,
 ) -> Option<R> {
     regex_match
-        .map(|m| m.as_str().parse::<usize>())
-        .map(|index| index.map(|i| call_back(i)).unwrap_or(None))
-        .flatten()
+        .and_then(|m| m.as_str().parse::<usize>().ok())
+        .and_then(call_back)
 }
@@ -1,7 +1,63 @@
+use crate::boost::boost;
+use crate::escape::{unescape, Escapes};
+use crate::near::near_operator;
 use crate::regex_approach::{regex_match_not_blank_query, regex_match_number};
-use crate::{Condition, Operator};
+use crate::term::{phrase_condition, term_condition, RANGE_PATTERN};
+use crate::{Boost, Condition, Operator};
 use eyre::Result;
 use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+// compiled once, parsing is dominated by the compilation of the regexes otherwise
+static REGEX_NEGATIVE_PHRASE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new("[-!！]\"(?P<phrase>[^\"]*)\"(?P<slop>[~～]\\d*)?(?P<boost>\\^[^\\s\"”()（）]*)?")
+        .unwrap()
+});
+static REGEX_PHRASE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        "(?P<required>\\+)?(?:(?P<target>[^\\s\"”()（）:：+-][^\\s\"”()（）:：]*)[:：])?\"(?P<phrase>[^\"]*)\"(?P<slop>[~～]\\d*)?(?P<boost>\\^[^\\s\"”()（）]*)?",
+    )
+    .unwrap()
+});
+static REGEX_TARGETED_RANGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        "(?P<target>[^\\s\"”()（）:：\\[\\]{{}}-][^\\s\"”()（）:：\\[\\]{{}}]*[:：])(?P<range>{})",
+        RANGE_PATTERN
+    ))
+    .unwrap()
+});
+static REGEX_NEGATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?P<head>^|[\s(])(?P<negation>(?i:[NＮ][OＯ][TＴ])\s*|[!！])(?P<next>[^\s)]*)")
+        .unwrap()
+});
+static REGEX_NOT_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?i:[NＮ][OＯ][TＴ])(?:$|\()").unwrap());
+static REGEX_WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[^\s()]+").unwrap());
+static REGEX_SYMBOLIC_AND: LazyLock<Regex> = LazyLock::new(|| Regex::new("^[&＆]{1,2}$").unwrap());
+static REGEX_SYMBOLIC_OR: LazyLock<Regex> = LazyLock::new(|| Regex::new("^[|｜]{1,2}$").unwrap());
+static REGEX_WHITESPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+// `-` just before the negative phrase is `NOT` rewritten by `normalize_negation`, so they cancel each other
+static REGEX_NEGATIVE_PHRASE_PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?P<negation>-)?”NPK:(?P<index>\d+)”").unwrap());
+static REGEX_PHRASE_PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"”PK:(?P<index>\d+)”").unwrap());
+static REGEX_OR_BETWEEN_SPACES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(" +(?i)[OＯ](?i)[RＲ] +").unwrap());
+static REGEX_SPACES: LazyLock<Regex> = LazyLock::new(|| Regex::new(" +").unwrap());
+static REGEX_NEGATIVE_PHRASE_KEYWORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^”NPK:(\d+)”(?:[~～](?P<slop>\d*))?(?:\^(?P<boost>[0-9.]+))?$").unwrap()
+});
+static REGEX_PHRASE_KEYWORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<sign>[-+])?(?:(?P<target>.+)[:：])?”PK:(?P<index>\d+)”(?:[~～](?P<slop>\d*))?(?:\^(?P<boost>[0-9.]+))?$",
+    )
+    .unwrap()
+});
+static REGEX_BOOST_AFTER_BRACKET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\^[^\s()"]*"#).unwrap());
+static REGEX_AND: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("^(?i)[AＡ](?i)[NＮ](?i)[DＤ]$").unwrap());
+static REGEX_OR: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(?i)[OＯ](?i)[RＲ]$").unwrap());
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct Query(String);
@@ -32,6 +88,84 @@ impl Query {
         )
     }
 
+    // the spaces in the ranges are replaced with `”` not to be split, like the placeholders of the phrase keywords
+    pub(crate) fn join_ranges(self) -> Self {
+        Self(
+            REGEX_TARGETED_RANGE
+                .replace_all(self.value_ref(), |captures: &Captures| {
+                    format!(
+                        "{}{}",
+                        &captures["target"],
+                        REGEX_WHITESPACES.replace_all(&captures["range"], "”")
+                    )
+                })
+                .into(),
+        )
+    }
+
+    // `&&`, `&`, `||` and `|` between spaces or brackets are rewritten to `AND` and `OR`,
+    // the spaces around them are added for `(A)||(B)`
+    pub(crate) fn normalize_symbolic_operators(self, symbolic_operators: bool) -> Self {
+        match symbolic_operators {
+            true => Self(
+                REGEX_WORD
+                    .replace_all(self.value_ref(), |captures: &Captures| match &captures[0] {
+                        word if REGEX_SYMBOLIC_AND.is_match(word) => " AND ".into(),
+                        word if REGEX_SYMBOLIC_OR.is_match(word) => " OR ".into(),
+                        word => word.to_string(),
+                    })
+                    .into(),
+            ),
+            false => self,
+        }
+    }
+
+    // `NOT A`, `NOT(A)` and `!A` are rewritten to `-A`, `NOT` without keyword on the right side is removed
+    // like `AND` and `OR`, and `!` alone is a keyword like `-`,
+    // `NOT` negates the sign of the keyword after it, so `NOT -A` and `NOT NOT A` are `A`, and `NOT +A` is `-A`
+    pub(crate) fn normalize_negation(self) -> Self {
+        Self(
+            REGEX_NEGATION
+                .replace_all(self.value_ref(), |captures: &Captures| {
+                    let (head, negation, next) =
+                        (&captures["head"], &captures["negation"], &captures["next"]);
+                    match (negation.starts_with(['!', '！']), next) {
+                        (true, "") => format!("{}{}", head, negation),
+                        (true, next) => format!("{}-{}", head, next),
+                        // `NOTE` and `NOT-A` are keywords
+                        (false, next)
+                            if negation.trim_end() == negation
+                                && !next.is_empty()
+                                && !next.starts_with('(') =>
+                        {
+                            captures[0].to_string()
+                        }
+                        (false, next)
+                            if next.is_empty()
+                                || REGEX_AND.is_match(next)
+                                || REGEX_OR.is_match(next) =>
+                        {
+                            format!("{}{}", head, next)
+                        }
+                        // `NEAR/n` just after `NOT` has no keyword on its left side, so both are removed
+                        (false, next) if near_operator(next).is_some() => head.to_string(),
+                        (false, next) if REGEX_NOT_PREFIX.is_match(next) => {
+                            format!("{}{}", head, next.chars().skip(3).collect::<String>())
+                        }
+                        (false, next) => match next.strip_prefix(['-', '!', '！']) {
+                            Some(keyword) if !keyword.is_empty() => format!("{}{}", head, keyword),
+                            _ => match next.strip_prefix('+').filter(|keyword| !keyword.is_empty())
+                            {
+                                Some(keyword) => format!("{}-{}", head, keyword),
+                                None => format!("{}-{}", head, next),
+                            },
+                        },
+                    }
+                })
+                .into(),
+        )
+    }
+
     fn remove_double_quotation(self) -> Self {
         Self(self.value().replace("\"", ""))
     }
@@ -41,36 +175,68 @@ impl Query {
     }
 
     pub(crate) fn is_not_blank(&self) -> bool {
-        self.value_ref()
-            .replace(" ", "")
-            .replace("　", "")
-            .is_empty()
-            == false
+        !self.value_ref().replace([' ', '　'], "").is_empty()
     }
 
     pub(crate) fn extract_phrase_keywords(self) -> Result<(Self, Vec<Query>, Vec<Query>)> {
         let mut query = self;
         let mut negative_phrase_keywords = Vec::<Query>::new();
         let mut phrase_keywords = Vec::<Query>::new();
-        vec![
+        [
             (
-                Regex::new("-\"([^\"]*)\"")?,
+                &*REGEX_NEGATIVE_PHRASE,
                 &mut negative_phrase_keywords,
                 "NPK",
             ),
-            (Regex::new("\"([^\"]*)\"")?, &mut phrase_keywords, "PK"),
+            (&*REGEX_PHRASE, &mut phrase_keywords, "PK"),
         ]
         .iter_mut()
         .for_each(|(regex, vec, prefix)| {
             query = Query::new(
                 regex
                     .replace_all(query.value_ref(), |captures: &Captures| {
-                        match regex_match_not_blank_query(captures.get(1)) {
+                        match regex_match_not_blank_query(captures.name("phrase")) {
                             Some(q) => {
                                 vec.push(q);
-                                format!(" ”{}:{}” ", prefix, vec.len())
+                                // the slop and the boost are kept just after the placeholder like the target before it,
+                                // and the text after the phrase which is not a boost is another keyword
+                                let slop = captures.name("slop").map_or("", |slop| slop.as_str());
+                                let (boost, rest) = match captures.name("boost") {
+                                    Some(b) if boost(b.as_str()).is_some() => (b.as_str(), ""),
+                                    Some(rest) => ("", rest.as_str()),
+                                    None => ("", ""),
+                                };
+                                let required = captures
+                                    .name("required")
+                                    .map_or("", |required| required.as_str());
+                                match captures.name("target") {
+                                    // keep the target next to the placeholder, so it's still attached after combining
+                                    Some(target) => format!(
+                                        "{}{}:”{}:{}”{}{} {}",
+                                        required,
+                                        target.as_str(),
+                                        prefix,
+                                        vec.len(),
+                                        slop,
+                                        boost,
+                                        rest
+                                    ),
+                                    None => format!(
+                                        " {}”{}:{}”{}{} {}",
+                                        required,
+                                        prefix,
+                                        vec.len(),
+                                        slop,
+                                        boost,
+                                        rest
+                                    ),
+                                }
                             }
-                            None => String::from(""),
+                            // the blank phrase is removed with its slop like it has never been typed,
+                            // and the text after it is a keyword even if it's a boost
+                            None => captures
+                                .name("boost")
+                                .map_or(String::from(""), |rest| format!(" {} ", rest.as_str())),
                         }
                     })
                     .to_string(),
@@ -81,19 +247,27 @@ impl Query {
     }
 
     pub(crate) fn combine_phrase_keywords(
-        self, negative_phrase_keywords: &Vec<Query>, phrase_keywords: &Vec<Query>,
+        self, negative_phrase_keywords: &[Query], phrase_keywords: &[Query],
     ) -> Result<Self> {
         let mut query = self;
-        vec![
-            (Regex::new(r"”NPK:(\d+)”")?, negative_phrase_keywords, "-"),
-            (Regex::new(r"”PK:(\d+)”")?, phrase_keywords, ""),
+        [
+            (
+                &*REGEX_NEGATIVE_PHRASE_PLACEHOLDER,
+                negative_phrase_keywords,
+                "-",
+            ),
+            (&*REGEX_PHRASE_PLACEHOLDER, phrase_keywords, ""),
         ]
         .into_iter()
         .for_each(|(regex, vec, prefix)| {
             query = Query::new(
                 regex
                     .replace_all(query.value_ref(), |captures: &Captures| {
-                        regex_match_number(captures.get(1), |i| {
+                        let prefix = match captures.name("negation") {
+                            Some(_) => "",
+                            None => prefix,
+                        };
+                        regex_match_number(captures.name("index"), |i| {
                             vec.get(i - 1)
                                 .map(|q| format!("{}\"{}\"", prefix, q.value_ref()))
                         })
@@ -105,36 +279,39 @@ impl Query {
         Ok(query)
     }
 
-    pub(crate) fn to_condition(self) -> Result<(bool, Condition, bool)> {
+    // the boost of `(A B)^2` is at the start of the query after the brackets, `^2a` is a keyword
+    pub(crate) fn take_boost(&mut self) -> Option<Boost> {
+        let text = REGEX_BOOST_AFTER_BRACKET.find(self.value_ref())?.as_str();
+        let boost = boost(text)?;
+        self.0 = self.0[text.len()..].to_string();
+        Some(boost)
+    }
+
+    // `OR` has the lowest priority, then `AND` and `NEAR/n`, which joins only the keywords next to it
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn to_condition(
+        self, escapes: &Escapes, exists_alias: Option<&str>,
+    ) -> Result<Condition> {
         let (mut query, negative_phrase_keywords, phrase_keywords) =
             self.extract_phrase_keywords()?;
 
-        query = Query::new(
-            Regex::new(" +(?i)[A|Ａ](?i)[N|Ｎ](?i)[D|Ｄ] +")?
-                .replace_all(query.value_ref(), |_: &Captures| String::from(" "))
-                .to_string(),
-        );
+        query = query.remove_dangling_near();
 
         let mut or_conditions = Vec::<Condition>::new();
-        let (is_start_with_or, is_end_with_or) = match (
-            Regex::new("^ *(?i)[O|Ｏ](?i)[R|Ｒ] *$")?.is_match(query.value_ref()),
-            Regex::new("^ *(?i)[O|Ｏ](?i)[R|Ｒ] +")?.is_match(query.value_ref()),
-            Regex::new(" +(?i)[O|Ｏ](?i)[R|Ｒ] *$")?.is_match(query.value_ref()),
-        ) {
-            (true, _, _) => (true, true),
-            (false, is_start_with_or, is_end_with_or) => (is_start_with_or, is_end_with_or),
-        };
-        let or_queries = Regex::new(" +(?i)[O|Ｏ](?i)[R|Ｒ] +")?
+        let or_queries = REGEX_OR_BETWEEN_SPACES
             .split(query.value_ref())
-            .into_iter()
             .collect::<Vec<&str>>();
-        let and_regex = Regex::new(" +")?;
+        let and_regex = &*REGEX_SPACES;
         or_queries.into_iter().for_each(|q| {
             let query = Query::new(q.into());
             if query.is_not_blank() {
-                let and_conditions = and_regex
+                // the keywords on both sides of `AND` are required, and it's ignored without them
+                let mut and_conditions = Vec::<(Condition, bool)>::new();
+                let mut and = false;
+                // `NEAR/n` joins the keywords on both sides of it, and it's ignored without them
+                let mut near = Option::<Operator>::None;
+                and_regex
                     .split(query.value_ref())
-                    .into_iter()
                     .filter_map(|k| {
                         let q = Query::new(k.into());
                         match q.is_not_blank() {
@@ -142,63 +319,196 @@ impl Query {
                             false => None,
                         }
                     })
-                    .filter_map(|keyword| {
-                        keyword
-                            .keyword_condition(&negative_phrase_keywords, &phrase_keywords)
-                            .unwrap_or(None)
-                    })
-                    .collect::<Vec<Condition>>();
-                or_conditions.push(Condition::Operator(Operator::And, and_conditions));
+                    .for_each(|keyword| {
+                        if let Some(operator) = near_operator(keyword.value_ref()) {
+                            near = Some(operator);
+                            and = false;
+                            return;
+                        }
+                        if REGEX_AND.is_match(keyword.value_ref()) {
+                            and = near.is_none() && !and_conditions.is_empty();
+                            return;
+                        }
+                        let condition = keyword
+                            .keyword_condition(
+                                &negative_phrase_keywords,
+                                &phrase_keywords,
+                                escapes,
+                                exists_alias,
+                            )
+                            .unwrap_or(None);
+                        if let Some(condition) = condition {
+                            let required = std::mem::take(&mut and);
+                            match (near.take(), and_conditions.pop()) {
+                                (Some(operator), Some((previous, previous_required))) => {
+                                    and_conditions.push((
+                                        Condition::Operator(operator, vec![previous, condition]),
+                                        previous_required,
+                                    ))
+                                }
+                                (_, previous) => {
+                                    and_conditions.extend(
+                                        previous.map(|(previous, r)| (previous, r || required)),
+                                    );
+                                    and_conditions.push((condition, required))
+                                }
+                            }
+                        }
+                    });
+                or_conditions.push(Condition::Operator(
+                    Operator::And,
+                    and_conditions
+                        .into_iter()
+                        .map(|(condition, required)| match required {
+                            true => condition.required_by_and(),
+                            false => condition,
+                        })
+                        .collect(),
+                ));
             }
         });
 
-        return Ok((
-            is_start_with_or,
-            Condition::Operator(Operator::Or, or_conditions).simplify(),
-            is_end_with_or,
-        ));
+        Ok(Condition::Operator(Operator::Or, or_conditions).simplify())
     }
 
+    // the keywords are replaced with their indexes in `conditions` and the operators are kept,
+    // so that the keywords of all the queries and brackets in a layer are joined by the priorities of the operators
+    pub(crate) fn index_keywords(
+        self, conditions: &mut Vec<Condition>, escapes: &Escapes, exists_alias: Option<&str>,
+    ) -> Result<String> {
+        let (query, negative_phrase_keywords, phrase_keywords) = self.extract_phrase_keywords()?;
+        let mut words = Vec::<String>::new();
+        for word in query.value_ref().split(' ').filter(|word| !word.is_empty()) {
+            if is_operator(word) {
+                words.push(word.into());
+            } else if let Some(condition) = Query::new(word.into())
+                .keyword_condition(
+                    &negative_phrase_keywords,
+                    &phrase_keywords,
+                    escapes,
+                    exists_alias,
+                )
+                .unwrap_or(None)
+            {
+                words.push(conditions.len().to_string());
+                conditions.push(condition);
+            }
+        }
+        Ok(format!(" {} ", words.join(" ")))
+    }
+
+    // `NEAR/n` next to `AND`, `OR` or another `NEAR/n` has no keywords to join, so it's removed
+    fn remove_dangling_near(self) -> Self {
+        let words = self
+            .value_ref()
+            .split(' ')
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>();
+        let is_operator = |k: Option<usize>| {
+            k.and_then(|k| words.get(k))
+                .is_some_and(|word| is_operator(word))
+        };
+        let kept = words
+            .iter()
+            .enumerate()
+            .filter(|&(k, word)| {
+                near_operator(word).is_none()
+                    || !(is_operator(Some(k + 1))
+                        || (is_operator(k.checked_sub(1)) && near_operator(words[k - 1]).is_none()))
+            })
+            .map(|(_, word)| *word)
+            .collect::<Vec<&str>>();
+        Self(format!(" {} ", kept.join(" ")))
+    }
+
+    // the escaped characters hidden by the placeholders are restored just before the keywords are parsed
     fn keyword_condition(
-        self, negative_phrase_keywords: &Vec<Query>, phrase_keywords: &Vec<Query>,
+        self, negative_phrase_keywords: &[Query], phrase_keywords: &[Query], escapes: &Escapes,
+        exists_alias: Option<&str>,
     ) -> Result<Option<Condition>> {
         Ok(
             match (
-                Regex::new(r"^”NPK:(\d+)”$")?.captures(self.value_ref()),
-                Regex::new(r"^”PK:(\d+)”$")?.captures(self.value_ref()),
+                REGEX_NEGATIVE_PHRASE_KEYWORD.captures(self.value_ref()),
+                REGEX_PHRASE_KEYWORD.captures(self.value_ref()),
             ) {
                 (Some(npk), _) => regex_match_number(npk.get(1), |i| {
-                    negative_phrase_keywords.get(i - 1).map(|npk| {
-                        Condition::Not(Box::new(Condition::PhraseKeyword(npk.value_ref().into())))
-                    })
-                }),
-                (_, Some(pk)) => regex_match_number(pk.get(1), |i| {
-                    phrase_keywords
+                    negative_phrase_keywords
                         .get(i - 1)
-                        .map(|pk| Condition::PhraseKeyword(pk.value_ref().into()))
+                        .map(|negative_phrase_keyword| {
+                            Condition::Not(Box::new(boosted(
+                                phrase_condition(
+                                    escapes.restore(negative_phrase_keyword.value_ref()),
+                                    npk.name("slop").map(|slop| slop.as_str()),
+                                ),
+                                &npk,
+                            )))
+                        })
                 }),
-                (None, None) => match (self.value_ref().len(), self.value_ref().starts_with("-")) {
-                    (1, _) => Some(Condition::Keyword(self.value())),
-                    (_, true) => Some(Condition::Not(Box::new(Condition::Keyword(
-                        self.value_ref()[1..self.value_ref().len()].into(),
-                    )))),
-                    _ => {
-                        let operation_regexes = vec![
-                            Regex::new("^(?i)[A|Ａ](?i)[N|Ｎ](?i)[D|Ｄ]$")?,
-                            Regex::new("^(?i)[O|Ｏ](?i)[R|Ｒ]$")?,
-                        ];
-                        operation_regexes
+                (_, Some(pk)) => regex_match_number(pk.name("index"), |i| {
+                    phrase_keywords.get(i - 1).map(|phrase_keyword| {
+                        let condition = boosted(
+                            phrase_condition(
+                                escapes.restore(phrase_keyword.value_ref()),
+                                pk.name("slop").map(|slop| slop.as_str()),
+                            ),
+                            &pk,
+                        );
+                        let condition = match pk.name("target") {
+                            Some(target) => Condition::Targeted(
+                                unescape(&escapes.restore(&target.as_str().replace('”', " ")), &[]),
+                                Box::new(condition),
+                            ),
+                            None => condition,
+                        };
+                        match pk.name("sign").map(|sign| sign.as_str()) {
+                            Some("-") => Condition::Not(Box::new(condition)),
+                            Some(_) => Condition::required(condition),
+                            None => condition,
+                        }
+                    })
+                }),
+                // the spaces of the ranges joined by `join_ranges` are restored
+                (None, None) => {
+                    let keyword = escapes.restore(&self.value().replace('”', " "));
+                    match (
+                        keyword.len(),
+                        keyword.strip_prefix('-'),
+                        keyword.strip_prefix('+'),
+                    ) {
+                        (1, _, _) => Some(term_condition(keyword, exists_alias)),
+                        (_, Some(negative_keyword), _) => Some(Condition::Not(Box::new(
+                            term_condition(negative_keyword.into(), exists_alias),
+                        ))),
+                        (_, _, Some(required_keyword)) => Some(Condition::required(
+                            term_condition(required_keyword.into(), exists_alias),
+                        )),
+                        _ => [&*REGEX_AND, &*REGEX_OR]
                             .into_iter()
-                            .find(|regex| regex.is_match(self.value_ref()))
+                            .find(|regex| regex.is_match(&keyword))
                             .map(|_| None)
-                            .unwrap_or(Some(Condition::Keyword(self.value())))
+                            .unwrap_or(Some(term_condition(keyword, exists_alias))),
                     }
-                },
+                }
             },
         )
     }
 }
 
+// `"phrase"^2`, the boost is on the phrase inside its target and sign
+fn boosted(condition: Condition, captures: &Captures) -> Condition {
+    match captures
+        .name("boost")
+        .and_then(|b| boost(&format!("^{}", b.as_str())))
+    {
+        Some(boost) => condition.boosted(boost),
+        None => condition,
+    }
+}
+
+fn is_operator(word: &str) -> bool {
+    REGEX_AND.is_match(word) || REGEX_OR.is_match(word) || near_operator(word).is_some()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +537,48 @@ mod tests {
         }
     }
 
+    mod test_join_ranges {
+        use super::*;
+
+        #[test]
+        fn test_join_ranges() {
+            let target = Query::new("price:[ 10 TO 100 ] -date:{2020-01-01 TO *} [1 TO 2]".into());
+            assert_eq!(
+                target.join_ranges(),
+                Query::new("price:[”10”TO”100”] -date:{2020-01-01”TO”*} [1 TO 2]".into())
+            )
+        }
+
+        #[test]
+        fn test_normalize_symbolic_operators() {
+            let target = Query::new("a && b||c (d)|(e) ＆ AT&T |||".into());
+            assert_eq!(
+                target.clone().normalize_symbolic_operators(true),
+                Query::new("a  AND  b||c (d) OR (e)  AND  AT&T |||".into())
+            );
+            assert_eq!(target.clone().normalize_symbolic_operators(false), target);
+        }
+
+        #[test]
+        fn test_normalize_negation() {
+            let target = Query::new("NOT a (not b) NOT(c) !d ! NOT OR e NOTE NOT".into());
+            assert_eq!(
+                target.normalize_negation(),
+                Query::new("-a (-b) -(c) -d ! OR e NOTE ".into())
+            )
+        }
+
+        #[test]
+        fn test_normalize_negation_of_signed_keyword() {
+            let target =
+                Query::new("NOT -a NOT !b* NOT +c NOT -(d) NOT NOT e NOT NOT(f) NOT -".into());
+            assert_eq!(
+                target.normalize_negation(),
+                Query::new("a b* -c (d)  e (f) --".into())
+            )
+        }
+    }
+
     mod test_extract_phrase_keywords {
         use super::*;
 
@@ -381,6 +733,42 @@ mod tests {
             assert_eq!(negative_phrase_keywords, vec![Query::new("ＮＰ１".into())]);
             assert_eq!(phrase_keywords, vec![Query::new("Ｐ１".into())])
         }
+
+        #[test]
+        fn test_extract_phrase_keywords_targeted_phrase_keyword() {
+            let target = Query::new("Ａ１ title:\"Ｐ１\" -author：\"ＮＰ１\" Ａ２".into());
+            let (query, negative_phrase_keywords, phrase_keywords) =
+                target.extract_phrase_keywords().unwrap();
+            assert_eq!(
+                query,
+                Query::new("Ａ１ title:”PK:1”  -author:”PK:2”  Ａ２".into())
+            );
+            assert_eq!(negative_phrase_keywords, vec![]);
+            assert_eq!(
+                phrase_keywords,
+                vec![Query::new("Ｐ１".into()), Query::new("ＮＰ１".into())]
+            )
+        }
+    }
+
+    mod test_extract_phrase_keywords_with_slop {
+        use super::*;
+
+        #[test]
+        fn test_extract_phrase_keywords_slop_next_to_placeholder() {
+            let target = Query::new("\"Ｐ１\"~5 -\"ＮＰ１\"～ title:\"Ｐ２\"~1Ａ１".into());
+            let (query, negative_phrase_keywords, phrase_keywords) =
+                target.extract_phrase_keywords().unwrap();
+            assert_eq!(
+                query,
+                Query::new(" ”PK:1”~5   ”NPK:1”～  title:”PK:2”~1 Ａ１".into())
+            );
+            assert_eq!(negative_phrase_keywords, vec![Query::new("ＮＰ１".into())]);
+            assert_eq!(
+                phrase_keywords,
+                vec![Query::new("Ｐ１".into()), Query::new("Ｐ２".into())]
+            )
+        }
     }
 
     mod test_combine_phrase_keywords {
@@ -391,11 +779,11 @@ mod tests {
             let target = Query::new("Ａ１ and Ａ２ or Ａ３".into());
             let query = target
                 .combine_phrase_keywords(
-                    &vec![
+                    &[
                         Query::new("否定的な連続キーワード１".into()),
                         Query::new("否定的な連続キーワード２".into()),
                     ],
-                    &vec![
+                    &[
                         Query::new("連続キーワード１".into()),
                         Query::new("連続キーワード２".into()),
                     ],
@@ -409,11 +797,11 @@ mod tests {
             let target = Query::new("Ａ１ ”PK:1” and ”NPK:1” Ａ２ ”PK:2” or ”NPK:2” Ａ３".into());
             let query = target
                 .combine_phrase_keywords(
-                    &vec![
+                    &[
                         Query::new("否定的な連続キーワード１".into()),
                         Query::new("否定的な連続キーワード２".into()),
                     ],
-                    &vec![
+                    &[
                         Query::new("連続キーワード１".into()),
                         Query::new("連続キーワード２".into()),
                     ],
@@ -427,8 +815,8 @@ mod tests {
             let target = Query::new("Ａ１ ”PK:1” and ”NPK:1” Ａ２ ”PK:2” or ”NPK:2” Ａ３".into());
             let query = target
                 .combine_phrase_keywords(
-                    &vec![Query::new("否定的な連続キーワード１".into())],
-                    &vec![Query::new("連続キーワード１".into())],
+                    &[Query::new("否定的な連続キーワード１".into())],
+                    &[Query::new("連続キーワード１".into())],
                 )
                 .unwrap();
             assert_eq!(
@@ -447,85 +835,64 @@ mod tests {
         #[test]
         fn test_query_to_condition_only_space() {
             let target = Query::new(" ".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(actual, (false, Condition::None, false))
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::None)
         }
 
         #[test]
         fn test_query_to_condition_only_one_keyword() {
             let target = Query::new("ＡＡＡ".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(actual, (false, Condition::Keyword("ＡＡＡ".into()), false))
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::Keyword("ＡＡＡ".into()))
         }
 
         #[test]
         fn test_query_to_condition_only_one_phrase_keyword() {
             let target = Query::new("\"ＡＡＡ ＢＢＢ\"".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(
-                actual,
-                (
-                    false,
-                    Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
-                    false
-                )
-            )
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()))
         }
 
         #[test]
         fn test_query_to_condition_only_one_phrase_keyword_include_special_word() {
             let target = Query::new("\" Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) \"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::PhraseKeyword(" Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) ".into()),
-                    false
-                )
+                Condition::PhraseKeyword(" Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) ".into())
             )
         }
 
         #[test]
         fn test_query_to_condition_only_one_phrase_keyword_include_full_width_special_word() {
             let target = Query::new("\"　Ｐ１　ａｎｄ　Ｐ２　−（Ｐ３　ｏｒ　Ｐ４）　\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::PhraseKeyword(
-                        "　Ｐ１　ａｎｄ　Ｐ２　−（Ｐ３　ｏｒ　Ｐ４）　".into()
-                    ),
-                    false
-                )
+                Condition::PhraseKeyword("　Ｐ１　ａｎｄ　Ｐ２　−（Ｐ３　ｏｒ　Ｐ４）　".into())
             )
         }
 
         #[test]
         fn test_query_to_condition_ten_phrase_keywords() {
             let target = Query::new("\"ＡＡＡ１\" \"ＡＡＡ２\" \"ＡＡＡ３\" \"ＡＡＡ４\" \"ＡＡＡ５\" \"ＡＡＡ６\" \"ＡＡＡ７\" \"ＡＡＡ８\" \"ＡＡＡ９\" \"ＡＡＡ１０\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::PhraseKeyword("ＡＡＡ１".into()),
-                            Condition::PhraseKeyword("ＡＡＡ２".into()),
-                            Condition::PhraseKeyword("ＡＡＡ３".into()),
-                            Condition::PhraseKeyword("ＡＡＡ４".into()),
-                            Condition::PhraseKeyword("ＡＡＡ５".into()),
-                            Condition::PhraseKeyword("ＡＡＡ６".into()),
-                            Condition::PhraseKeyword("ＡＡＡ７".into()),
-                            Condition::PhraseKeyword("ＡＡＡ８".into()),
-                            Condition::PhraseKeyword("ＡＡＡ９".into()),
-                            Condition::PhraseKeyword("ＡＡＡ１０".into()),
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::PhraseKeyword("ＡＡＡ１".into()),
+                        Condition::PhraseKeyword("ＡＡＡ２".into()),
+                        Condition::PhraseKeyword("ＡＡＡ３".into()),
+                        Condition::PhraseKeyword("ＡＡＡ４".into()),
+                        Condition::PhraseKeyword("ＡＡＡ５".into()),
+                        Condition::PhraseKeyword("ＡＡＡ６".into()),
+                        Condition::PhraseKeyword("ＡＡＡ７".into()),
+                        Condition::PhraseKeyword("ＡＡＡ８".into()),
+                        Condition::PhraseKeyword("ＡＡＡ９".into()),
+                        Condition::PhraseKeyword("ＡＡＡ１０".into()),
+                    ]
                 )
             )
         }
@@ -533,44 +900,32 @@ mod tests {
         #[test]
         fn test_query_to_condition_only_one_negative_keyword() {
             let target = Query::new("-ＡＡＡ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
-                    false
-                )
+                Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into())))
             )
         }
 
         #[test]
         fn test_query_to_condition_only_one_negative_phrase_keyword() {
             let target = Query::new("-\"ＡＡＡ ＢＢＢ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()))),
-                    false
-                )
+                Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into())))
             )
         }
 
         #[test]
         fn test_query_to_condition_only_one_negative_phrase_keyword_include_special_word() {
             let target = Query::new("-\" ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) \"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Not(Box::new(Condition::PhraseKeyword(
-                        " ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) ".into()
-                    ))),
-                    false
-                )
+                Condition::Not(Box::new(Condition::PhraseKeyword(
+                    " ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) ".into()
+                )))
             )
         }
 
@@ -579,43 +934,35 @@ mod tests {
         ) {
             let target =
                 Query::new("-\"　ＮＰ１　ａｎｄ　ＮＰ２　−（ＮＰ３　ｏｒ　ＮＰ４）　\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Not(Box::new(Condition::PhraseKeyword(
-                        "　ＮＰ１　ａｎｄ　ＮＰ２　−（ＮＰ３　ｏｒ　ＮＰ４）　".into()
-                    ))),
-                    false
-                )
+                Condition::Not(Box::new(Condition::PhraseKeyword(
+                    "　ＮＰ１　ａｎｄ　ＮＰ２　−（ＮＰ３　ｏｒ　ＮＰ４）　".into()
+                )))
             )
         }
 
         #[test]
         fn test_query_to_condition_ten_negative_phrase_keywords() {
             let target = Query::new("-\"ＡＡＡ１\" -\"ＡＡＡ２\" -\"ＡＡＡ３\" -\"ＡＡＡ４\" -\"ＡＡＡ５\" -\"ＡＡＡ６\" -\"ＡＡＡ７\" -\"ＡＡＡ８\" -\"ＡＡＡ９\" -\"ＡＡＡ１０\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ１".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ２".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ３".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ４".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ５".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ６".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ７".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ８".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ９".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ１０".into()))),
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ１".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ２".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ３".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ４".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ５".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ６".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ７".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ８".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ９".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ１０".into()))),
+                    ]
                 )
             )
         }
@@ -623,19 +970,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords() {
             let target = Query::new("ＡＡＡ ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -643,19 +986,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_phrase_keywords() {
             let target = Query::new("\"ＡＡＡ ＢＢＢ\" \"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
-                            Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
+                        Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())
+                    ]
                 )
             )
         }
@@ -663,19 +1002,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_keywords() {
             let target = Query::new("-ＡＡＡ -ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
-                            Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
+                        Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
+                    ]
                 )
             )
         }
@@ -683,23 +1018,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_phrase_keywords() {
             let target = Query::new("-\"ＡＡＡ ＢＢＢ\" -\"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＡＡＡ ＢＢＢ".into()
-                            ))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＣＣＣ ＤＤＤ".into()
-                            )))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())))
+                    ]
                 )
             )
         }
@@ -707,21 +1034,17 @@ mod tests {
         #[test]
         fn test_query_to_condition_multi_keywords() {
             let target = Query::new("ＡＡＡ \"ＢＢＢ\" -\"ＣＣＣ\" -ＤＤＤ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::PhraseKeyword("ＢＢＢ".into()),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ".into()))),
-                            Condition::Not(Box::new(Condition::Keyword("ＤＤＤ".into())))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::PhraseKeyword("ＢＢＢ".into()),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ".into()))),
+                        Condition::Not(Box::new(Condition::Keyword("ＤＤＤ".into())))
+                    ]
                 )
             )
         }
@@ -730,23 +1053,19 @@ mod tests {
         fn test_query_to_condition_multi_keywords_without_space() {
             let target =
                 Query::new("ＡＡＡ\"ＢＢＢ\"\"ｂｂｂ\"-\"ＣＣＣ\"-\"ｃｃｃ\"-ＤＤＤ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::PhraseKeyword("ＢＢＢ".into()),
-                            Condition::PhraseKeyword("ｂｂｂ".into()),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ".into()))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword("ｃｃｃ".into()))),
-                            Condition::Not(Box::new(Condition::Keyword("ＤＤＤ".into())))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::PhraseKeyword("ＢＢＢ".into()),
+                        Condition::PhraseKeyword("ｂｂｂ".into()),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ｃｃｃ".into()))),
+                        Condition::Not(Box::new(Condition::Keyword("ＤＤＤ".into())))
+                    ]
                 )
             )
         }
@@ -754,19 +1073,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords_with_or() {
             let target = Query::new("ＡＡＡ or ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -774,19 +1089,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_phrase_keywords_with_or() {
             let target = Query::new("\"ＡＡＡ ＢＢＢ\" or \"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
-                            Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
+                        Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())
+                    ]
                 )
             )
         }
@@ -794,19 +1105,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_keywords_with_or() {
             let target = Query::new("-ＡＡＡ or -ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
-                            Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
+                        Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
+                    ]
                 )
             )
         }
@@ -814,23 +1121,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_phrase_keywords_with_or() {
             let target = Query::new("-\"ＡＡＡ ＢＢＢ\" or -\"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＡＡＡ ＢＢＢ".into()
-                            ))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＣＣＣ ＤＤＤ".into()
-                            )))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())))
+                    ]
                 )
             )
         }
@@ -838,19 +1137,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords_with_double_or() {
             let target = Query::new("ＡＡＡ or or ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -858,19 +1153,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords_with_and() {
             let target = Query::new("ＡＡＡ and ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::required(Condition::Keyword("ＡＡＡ".into())),
+                        Condition::required(Condition::Keyword("ＢＢＢ".into()))
+                    ]
                 )
             )
         }
@@ -878,19 +1169,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_phrase_keywords_with_and() {
             let target = Query::new("\"ＡＡＡ ＢＢＢ\" and \"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()),
-                            Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::required(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into())),
+                        Condition::required(Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into()))
+                    ]
                 )
             )
         }
@@ -898,19 +1185,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_keywords_with_and() {
             let target = Query::new("-ＡＡＡ and -ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
-                            Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::Keyword("ＡＡＡ".into()))),
+                        Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into())))
+                    ]
                 )
             )
         }
@@ -918,23 +1201,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_negative_phrase_keywords_with_and() {
             let target = Query::new("-\"ＡＡＡ ＢＢＢ\" and -\"ＣＣＣ ＤＤＤ\"".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＡＡＡ ＢＢＢ".into()
-                            ))),
-                            Condition::Not(Box::new(Condition::PhraseKeyword(
-                                "ＣＣＣ ＤＤＤ".into()
-                            )))
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＡＡＡ ＢＢＢ".into()))),
+                        Condition::Not(Box::new(Condition::PhraseKeyword("ＣＣＣ ＤＤＤ".into())))
+                    ]
                 )
             )
         }
@@ -942,19 +1217,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords_with_double_and() {
             let target = Query::new("ＡＡＡ and and ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::required(Condition::Keyword("ＡＡＡ".into())),
+                        Condition::required(Condition::Keyword("ＢＢＢ".into()))
+                    ]
                 )
             )
         }
@@ -964,40 +1235,36 @@ mod tests {
             let target = Query::new(
                 "ＡＡＡ and ＢＢＢ or ＣＣＣ ＤＤＤ and ＥＥＥ or ＦＦＦ or ＧＧＧ ＨＨＨ".into(),
             );
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Operator(
-                                Operator::And,
-                                vec![
-                                    Condition::Keyword("ＡＡＡ".into()),
-                                    Condition::Keyword("ＢＢＢ".into())
-                                ]
-                            ),
-                            Condition::Operator(
-                                Operator::And,
-                                vec![
-                                    Condition::Keyword("ＣＣＣ".into()),
-                                    Condition::Keyword("ＤＤＤ".into()),
-                                    Condition::Keyword("ＥＥＥ".into())
-                                ]
-                            ),
-                            Condition::Keyword("ＦＦＦ".into()),
-                            Condition::Operator(
-                                Operator::And,
-                                vec![
-                                    Condition::Keyword("ＧＧＧ".into()),
-                                    Condition::Keyword("ＨＨＨ".into())
-                                ]
-                            ),
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::required(Condition::Keyword("ＡＡＡ".into())),
+                                Condition::required(Condition::Keyword("ＢＢＢ".into()))
+                            ]
+                        ),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("ＣＣＣ".into()),
+                                Condition::required(Condition::Keyword("ＤＤＤ".into())),
+                                Condition::required(Condition::Keyword("ＥＥＥ".into()))
+                            ]
+                        ),
+                        Condition::Keyword("ＦＦＦ".into()),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Keyword("ＧＧＧ".into()),
+                                Condition::Keyword("ＨＨＨ".into())
+                            ]
+                        ),
+                    ]
                 )
             )
         }
@@ -1005,19 +1272,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_two_keywords_with_double_and_or() {
             let target = Query::new("ＡＡＡ and or and or ＢＢＢ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -1027,21 +1290,17 @@ mod tests {
             let target = Query::new(
                 "ＡＡＡ \" and ＢＢＢ or ＣＣＣ and \" \" or ＤＤＤ and ＥＥＥ or \" ＦＦＦ".into(),
             );
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::PhraseKeyword(" and ＢＢＢ or ＣＣＣ and ".into()),
-                            Condition::PhraseKeyword(" or ＤＤＤ and ＥＥＥ or ".into()),
-                            Condition::Keyword("ＦＦＦ".into()),
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::PhraseKeyword(" and ＢＢＢ or ＣＣＣ and ".into()),
+                        Condition::PhraseKeyword(" or ＤＤＤ and ＥＥＥ or ".into()),
+                        Condition::Keyword("ＦＦＦ".into()),
+                    ]
                 )
             )
         }
@@ -1049,43 +1308,39 @@ mod tests {
         #[test]
         fn test_query_to_condition_full_pattern() {
             let target = Query::new(" ＡＡＡ  Ａｎｄ -ＢＢＢ ＡnＤ ＣorＣ  ｃｃｃ Ｏr  \"c1 and c2\"  -\"c3 or c4\"  ＤandＤ anD \" Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) \"  ａnｄ  -\" ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) \"  oＲ  ＩＩＩ and ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::Or,
-                        vec![
-                            Condition::Operator(
-                                Operator::And,
-                                vec![
-                                    Condition::Keyword("ＡＡＡ".into()),
-                                    Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into()))),
-                                    Condition::Keyword("ＣorＣ".into()),
-                                    Condition::Keyword("ｃｃｃ".into()),
-                                ]
-                            ),
-                            Condition::Operator(
-                                Operator::And,
-                                vec![
-                                    Condition::PhraseKeyword("c1 and c2".into()),
-                                    Condition::Not(Box::new(Condition::PhraseKeyword(
-                                        "c3 or c4".into()
-                                    ))),
-                                    Condition::Keyword("ＤandＤ".into()),
-                                    Condition::PhraseKeyword(
-                                        " Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) ".into()
-                                    ),
-                                    Condition::Not(Box::new(Condition::PhraseKeyword(
-                                        " ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) ".into()
-                                    )))
-                                ]
-                            ),
-                            Condition::Keyword("ＩＩＩ".into()),
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::required(Condition::Keyword("ＡＡＡ".into())),
+                                Condition::Not(Box::new(Condition::Keyword("ＢＢＢ".into()))),
+                                Condition::required(Condition::Keyword("ＣorＣ".into())),
+                                Condition::Keyword("ｃｃｃ".into()),
+                            ]
+                        ),
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::PhraseKeyword("c1 and c2".into()),
+                                Condition::Not(Box::new(Condition::PhraseKeyword(
+                                    "c3 or c4".into()
+                                ))),
+                                Condition::required(Condition::Keyword("ＤandＤ".into())),
+                                Condition::required(Condition::PhraseKeyword(
+                                    " Ｐ１ and Ｐ２ -(Ｐ３ or Ｐ４) ".into()
+                                )),
+                                Condition::Not(Box::new(Condition::PhraseKeyword(
+                                    " ＮＰ１ and ＮＰ２ -(ＮＰ３ or ＮＰ４) ".into()
+                                )))
+                            ]
+                        ),
+                        Condition::Keyword("ＩＩＩ".into()),
+                    ]
                 )
             )
         }
@@ -1093,19 +1348,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_start_end_with_and() {
             let target = Query::new("and ＡＡＡ ＢＢＢ and".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -1113,19 +1364,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_start_end_with_and_with_space() {
             let target = Query::new(" and ＡＡＡ ＢＢＢ and ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    false,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    false
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -1133,19 +1380,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_start_end_with_or() {
             let target = Query::new("or ＡＡＡ ＢＢＢ or".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    true,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    true
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -1153,19 +1396,15 @@ mod tests {
         #[test]
         fn test_query_to_condition_start_end_with_or_with_space() {
             let target = Query::new(" or ＡＡＡ ＢＢＢ or ".into());
-            let actual = target.to_condition().unwrap();
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
             assert_eq!(
                 actual,
-                (
-                    true,
-                    Condition::Operator(
-                        Operator::And,
-                        vec![
-                            Condition::Keyword("ＡＡＡ".into()),
-                            Condition::Keyword("ＢＢＢ".into())
-                        ]
-                    ),
-                    true
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Keyword("ＡＡＡ".into()),
+                        Condition::Keyword("ＢＢＢ".into())
+                    ]
                 )
             )
         }
@@ -1173,22 +1412,72 @@ mod tests {
         #[test]
         fn test_query_to_condition_start_end_with_or_with_space_include_one_keyword() {
             let target = Query::new(" or ＡＡＡ or ".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(actual, (true, Condition::Keyword("ＡＡＡ".into()), true))
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::Keyword("ＡＡＡ".into()))
         }
 
         #[test]
         fn test_query_to_condition_only_or() {
             let target = Query::new("or".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(actual, (true, Condition::None, true))
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::None)
         }
 
         #[test]
         fn test_query_to_condition_only_or_with_space() {
             let target = Query::new(" or ".into());
-            let actual = target.to_condition().unwrap();
-            assert_eq!(actual, (true, Condition::None, true))
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(actual, Condition::None)
+        }
+
+        #[test]
+        fn test_query_to_condition_targeted_phrase_keywords() {
+            let target = Query::new("title:\"Ｐ１\" -author:\"ＮＰ１\"".into());
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::And,
+                    vec![
+                        Condition::Targeted(
+                            "title".into(),
+                            Box::new(Condition::PhraseKeyword("Ｐ１".into()))
+                        ),
+                        Condition::Not(Box::new(Condition::Targeted(
+                            "author".into(),
+                            Box::new(Condition::PhraseKeyword("ＮＰ１".into()))
+                        ))),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn test_query_to_condition_near() {
+            let target = Query::new("NEAR/3 Ａ ONEAR/2 Ｂ Ｃ NEAR/1 OR Ｄ NEAR/5".into());
+            let actual = target.to_condition(&Escapes::default(), None).unwrap();
+            assert_eq!(
+                actual,
+                Condition::Operator(
+                    Operator::Or,
+                    vec![
+                        Condition::Operator(
+                            Operator::And,
+                            vec![
+                                Condition::Operator(
+                                    Operator::OrderedNear(2),
+                                    vec![
+                                        Condition::Keyword("Ａ".into()),
+                                        Condition::Keyword("Ｂ".into())
+                                    ]
+                                ),
+                                Condition::Keyword("Ｃ".into()),
+                            ]
+                        ),
+                        Condition::Keyword("Ｄ".into()),
+                    ]
+                )
+            )
         }
     }
 }
@@ -0,0 +1,96 @@
+use crate::{Span, SyntaxError, Warning};
+use std::fmt::{Display, Formatter};
+
+impl SyntaxError {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::UnbalancedBracket(span)
+            | SyntaxError::UnterminatedQuote(span)
+            | SyntaxError::DanglingOperator(span)
+            | SyntaxError::EmptyGroup(span)
+            | SyntaxError::EmptyPhrase(span)
+            | SyntaxError::InvalidRegex(span)
+            | SyntaxError::TooManyEdits(span) => *span,
+        }
+    }
+
+    pub(crate) fn with_span(self, span: Span) -> Self {
+        match self {
+            SyntaxError::UnbalancedBracket(_) => SyntaxError::UnbalancedBracket(span),
+            SyntaxError::UnterminatedQuote(_) => SyntaxError::UnterminatedQuote(span),
+            SyntaxError::DanglingOperator(_) => SyntaxError::DanglingOperator(span),
+            SyntaxError::EmptyGroup(_) => SyntaxError::EmptyGroup(span),
+            SyntaxError::EmptyPhrase(_) => SyntaxError::EmptyPhrase(span),
+            SyntaxError::InvalidRegex(_) => SyntaxError::InvalidRegex(span),
+            SyntaxError::TooManyEdits(_) => SyntaxError::TooManyEdits(span),
+        }
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            SyntaxError::UnbalancedBracket(_) => "unbalanced bracket",
+            SyntaxError::UnterminatedQuote(_) => "unterminated double quotation",
+            SyntaxError::DanglingOperator(_) => "operator without keyword",
+            SyntaxError::EmptyGroup(_) => "empty brackets",
+            SyntaxError::EmptyPhrase(_) => "empty phrase keyword",
+            SyntaxError::InvalidRegex(_) => "invalid regular expression",
+            SyntaxError::TooManyEdits(_) => "too many edits of fuzzy keyword",
+        };
+        write!(f, "{} at {}", message, self.span().start)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+// what the lenient parser did to correct the error
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let correction = match self.error {
+            SyntaxError::UnbalancedBracket(_) => "removed unmatched bracket",
+            SyntaxError::UnterminatedQuote(_) => "ignored unmatched double quotation",
+            SyntaxError::DanglingOperator(_) => "dropped dangling",
+            SyntaxError::EmptyGroup(_) => "removed empty brackets",
+            SyntaxError::EmptyPhrase(_) => "ignored empty phrase",
+            SyntaxError::InvalidRegex(_) => "ignored invalid regex",
+            SyntaxError::TooManyEdits(_) => "reduced max edits of",
+        };
+        write!(
+            f,
+            "{} `{}` at {}",
+            correction,
+            self.text,
+            self.error.span().start
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod test_display {
+        use super::*;
+
+        #[test]
+        fn test_display() {
+            assert_eq!(
+                SyntaxError::UnbalancedBracket(Span { start: 7, end: 8 }).to_string(),
+                "unbalanced bracket at 7"
+            )
+        }
+
+        #[test]
+        fn test_display_warning() {
+            assert_eq!(
+                Warning {
+                    error: SyntaxError::DanglingOperator(Span { start: 2, end: 4 }),
+                    text: "OR".into(),
+                }
+                .to_string(),
+                "dropped dangling `OR` at 2"
+            )
+        }
+    }
+}
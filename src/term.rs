@@ -0,0 +1,599 @@
+use crate::boost::split_boost;
+use crate::escape::{unescape, COLONS};
+use crate::{Comparison, Condition};
+use regex::Regex;
+use std::sync::LazyLock;
+
+// `[10 TO 100]` and `{2020-01-01 TO *}`, the bounds can't have spaces and brackets unless they are escaped
+pub(crate) const RANGE_PATTERN: &str =
+    r"[\[{]\s*(?:\\.|[^\s\[\]{}()\\])+\s+TO\s+(?:\\.|[^\s\[\]{}()\\])+\s*[\]}]";
+
+static REGEX_RANGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<target>(?:\\.|[^\s:：\\])+)[:：](?P<open>[\[{])\s*(?P<lower>(?:\\.|[^\s\[\]{}()\\])+)\s+TO\s+(?P<upper>(?:\\.|[^\s\[\]{}()\\])+)\s*(?P<close>[\]}])$")
+        .unwrap()
+});
+
+// `title:rust` is split into `title` and `rust`, only when there is one target, the escaped colons are not targets
+pub(crate) fn split_target(term: &str) -> Option<(&str, &str)> {
+    let mut parts = Vec::<&str>::new();
+    let mut start = 0;
+    for (i, colon) in unescaped_indices(term, &COLONS) {
+        parts.push(&term[start..i]);
+        start = i + colon.len_utf8();
+    }
+    parts.push(&term[start..]);
+    match parts
+        .into_iter()
+        .filter(|it| !it.is_empty())
+        .collect::<Vec<&str>>()[..]
+    {
+        [target, value] => Some((target, value)),
+        _ => None,
+    }
+}
+
+// the characters which have special meanings in a keyword, escaped by `\`
+pub(crate) const SPECIAL_CHARS: [char; 4] = ['*', '?', '~', '～'];
+
+// `_exists_:title` is the condition of the field, not a keyword
+pub(crate) const EXISTS_TARGET: &str = "_exists_";
+
+// lucene doesn't support more edits
+const MAX_EDITS: u8 = 2;
+
+// the condition of a keyword which is not a phrase, `title:rust` is the keyword `rust` targeted by `title`,
+// `rust~1` is fuzzy, `*` and `?` are wildcards and `rust*` is a prefix,
+// the characters escaped by `\` are restored, and the wildcard keeps the escapes of `*`, `?` and `\`,
+// `rust^2` is boosted, and `has:title` is `_exists_:title` with the alias `has`
+pub(crate) fn term_condition(term: String, exists_alias: Option<&str>) -> Condition {
+    // the weight of `_exists_:title^2` is a part of the target, and the escaped name like `\has:title` is a target
+    if let Some(condition) = std::iter::once(EXISTS_TARGET)
+        .chain(exists_alias)
+        .find_map(|name| exists_condition(&term, name))
+    {
+        return condition;
+    }
+    // `^` in the pattern is not a boost
+    if let Some(regex) = regex_condition(&term) {
+        return regex.unwrap_or(Condition::None);
+    }
+    if let Some((term, boost)) = split_boost(&term) {
+        return term_condition(term.into(), exists_alias).boosted(boost);
+    }
+    if let Some(condition) = range_condition(&term).or_else(|| comparison_condition(&term)) {
+        return condition;
+    }
+    match split_target(&term) {
+        Some((target, value)) => {
+            Condition::Targeted(unescape(target, &[]), Box::new(value_condition(value)))
+        }
+        None => value_condition(&term),
+    }
+}
+
+fn value_condition(value: &str) -> Condition {
+    if let Some((term, max_edits)) = split_max_edits(value) {
+        return Condition::Fuzzy {
+            term: unescape(term, &[]),
+            max_edits,
+        };
+    }
+    match unescaped_indices(value, &['*', '?'])[..] {
+        [] => Condition::Keyword(unescape(value, &[])),
+        [(index, '*')] if index > 0 && index + 1 == value.len() => {
+            Condition::Prefix(unescape(&value[..index], &[]))
+        }
+        _ => Condition::Wildcard(unescape(value, &['\\', '*', '?'])),
+    }
+}
+static REGEX_COMPARISON: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<target>(?:\\.|[^\s:：\\])+)[:：](?P<comparison>[>＞<＜][=＝]?)(?P<value>(?:\\.|[^\s=＝>＞<＜\\])(?:\\.|[^\s\\])*\\?)$",
+    )
+    .unwrap()
+});
+
+// `name:title` is `Condition::Exists` of `title`, the name is `_exists_` or the alias like `has`
+pub(crate) fn exists_condition(term: &str, name: &str) -> Option<Condition> {
+    match split_target(term) {
+        Some((target, field)) if target == name => Some(Condition::Exists {
+            target: unescape(field, &[]),
+        }),
+        _ => None,
+    }
+}
+
+// the invalid pattern is not a keyword, it's dropped and reported by the bnf approach
+pub(crate) fn is_invalid_regex(term: &str) -> bool {
+    match regex_condition(term) {
+        Some(regex) => regex.is_err(),
+        None => {
+            split_boost(term).is_some_and(|(term, _)| matches!(regex_condition(term), Some(Err(_))))
+        }
+    }
+}
+
+// `/err(or)?\d+/` and `title:/pattern/`, the target is before the first colon,
+// and the backslashes of the pattern are kept except the one of `\/`
+fn regex_condition(term: &str) -> Option<Result<Condition, regex::Error>> {
+    let (target, value) = match unescaped_indices(term, &COLONS).first() {
+        Some(&(index, colon)) if index > 0 && term[index + colon.len_utf8()..].starts_with('/') => {
+            (Some(&term[..index]), &term[index + colon.len_utf8()..])
+        }
+        _ => (None, term),
+    };
+    let slashes = unescaped_indices(value, &['/']);
+    let pattern = match slashes[..] {
+        [(0, _), (last, _)] if last + 1 == value.len() && last > 1 => &value[1..last],
+        _ => return None,
+    };
+    let pattern = pattern.replace("\\/", "/");
+    Some(Regex::new(&pattern).map(|_| {
+        let condition = Condition::Regex(pattern);
+        match target {
+            Some(target) => Condition::Targeted(unescape(target, &[]), Box::new(condition)),
+            None => condition,
+        }
+    }))
+}
+
+// `[` and `]` include the bounds, `{` and `}` exclude them
+fn range_condition(term: &str) -> Option<Condition> {
+    let captures = REGEX_RANGE.captures(term)?;
+    let bound = |name: &str| {
+        captures
+            .name(name)
+            .map(|bound| bound.as_str())
+            .filter(|bound| *bound != "*")
+            .map(|bound| unescape(bound, &[]))
+    };
+    Some(Condition::Range {
+        target: unescape(&captures["target"], &[]),
+        lower: bound("lower"),
+        upper: bound("upper"),
+        inclusive_lower: &captures["open"] == "[",
+        inclusive_upper: &captures["close"] == "]",
+    })
+}
+
+// full width `＞`, `＜` and `＝` are the same as the half width ones
+fn comparison_condition(term: &str) -> Option<Condition> {
+    let captures = REGEX_COMPARISON.captures(term)?;
+    let comparison = match captures["comparison"].replace('＝', "=").as_str() {
+        ">" | "＞" => Comparison::GreaterThan,
+        ">=" | "＞=" => Comparison::GreaterThanOrEqual,
+        "<" | "＜" => Comparison::LessThan,
+        _ => Comparison::LessThanOrEqual,
+    };
+    Some(Condition::Comparison {
+        target: unescape(&captures["target"], &[]),
+        comparison,
+        value: unescape(&captures["value"], &[]),
+    })
+}
+
+// the slop is the digits after `~` of `"quick fox"~5`, it's a normal phrase when they are omitted or 0
+pub(crate) fn phrase_condition(phrase: String, slop: Option<&str>) -> Condition {
+    match slop.filter(|slop| !slop.is_empty()).map(|slop| {
+        // only the digits are given, so it fails only when it's too large
+        slop.parse::<u32>().unwrap_or(u32::MAX)
+    }) {
+        None | Some(0) => Condition::PhraseKeyword(unescape(&phrase, &[])),
+        Some(slop) => Condition::Proximity {
+            phrase: unescape(&phrase, &[]),
+            slop,
+        },
+    }
+}
+
+// `rust~` and `rust~1`, the max edits is `MAX_EDITS` when it's omitted or too large
+fn split_max_edits(value: &str) -> Option<(&str, u8)> {
+    let (term, max_edits) = split_tilde(value)?;
+    Some((
+        term,
+        max_edits
+            .parse::<u8>()
+            .map_or(MAX_EDITS, |max_edits| max_edits.min(MAX_EDITS)),
+    ))
+}
+
+// the term and the digits after the last tilde
+fn split_tilde(value: &str) -> Option<(&str, &str)> {
+    let (index, tilde) = unescaped_indices(value, &['~', '～']).pop()?;
+    let (term, max_edits) = (&value[..index], &value[index + tilde.len_utf8()..]);
+    (!term.is_empty() && max_edits.chars().all(|c| c.is_ascii_digit())).then_some((term, max_edits))
+}
+
+// `rust~5` is fuzzy with `MAX_EDITS`, the larger max edits is corrected and reported by the bnf approach
+pub(crate) fn is_too_many_edits(term: &str, condition: &Condition) -> bool {
+    fn is_fuzzy(condition: &Condition) -> bool {
+        match condition {
+            Condition::Fuzzy { .. } => true,
+            Condition::Targeted(_, condition) | Condition::Boosted { condition, .. } => {
+                is_fuzzy(condition)
+            }
+            _ => false,
+        }
+    }
+    let term = split_boost(term).map_or(term, |(term, _)| term);
+    is_fuzzy(condition)
+        && split_tilde(term).is_some_and(|(_, max_edits)| {
+            !max_edits.is_empty()
+                && max_edits
+                    .parse::<u8>()
+                    .map_or(true, |max_edits| max_edits > MAX_EDITS)
+        })
+}
+
+pub(crate) fn unescaped_indices(value: &str, chars: &[char]) -> Vec<(usize, char)> {
+    let mut escaped = false;
+    value
+        .char_indices()
+        .filter_map(|(i, c)| {
+            let is_unescaped = !escaped && chars.contains(&c);
+            escaped = !escaped && c == '\\';
+            is_unescaped.then_some((i, c))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boost;
+
+    mod test_term_condition {
+        use super::*;
+
+        #[test]
+        fn test_term_condition_keyword() {
+            assert_eq!(
+                term_condition("title:rust".into(), None),
+                Condition::Targeted("title".into(), Box::new(Condition::Keyword("rust".into())))
+            );
+            assert_eq!(
+                term_condition("rust\\*\\?\\~".into(), None),
+                Condition::Keyword("rust*?~".into())
+            );
+            assert_eq!(
+                term_condition("a\\:b\\^2 \\\\\\:c".into(), None),
+                Condition::Keyword("a:b^2 \\:c".into())
+            );
+        }
+
+        #[test]
+        fn test_term_condition_prefix() {
+            assert_eq!(
+                term_condition("rust*".into(), None),
+                Condition::Prefix("rust".into())
+            );
+            assert_eq!(
+                term_condition("title：ru\\*st*".into(), None),
+                Condition::Targeted("title".into(), Box::new(Condition::Prefix("ru*st".into())))
+            );
+        }
+
+        #[test]
+        fn test_term_condition_wildcard() {
+            vec!["te?t", "*rust", "ru*st*", "*", "ru\\*st?"]
+                .into_iter()
+                .for_each(|term| {
+                    assert_eq!(
+                        term_condition(term.into(), None),
+                        Condition::Wildcard(term.into()),
+                        "{}",
+                        term
+                    )
+                });
+            assert_eq!(
+                term_condition("title:*".into(), None),
+                Condition::Targeted("title".into(), Box::new(Condition::Wildcard("*".into())))
+            );
+        }
+
+        #[test]
+        fn test_term_condition_range() {
+            assert_eq!(
+                term_condition("price^2:[10 TO 100]".into(), None),
+                Condition::Range {
+                    target: "price^2".into(),
+                    lower: Some("10".into()),
+                    upper: Some("100".into()),
+                    inclusive_lower: true,
+                    inclusive_upper: true,
+                }
+            );
+            assert_eq!(
+                term_condition("date：{ 2020-01-01　TO * ]".into(), None),
+                Condition::Range {
+                    target: "date".into(),
+                    lower: Some("2020-01-01".into()),
+                    upper: None,
+                    inclusive_lower: false,
+                    inclusive_upper: true,
+                }
+            );
+            assert_eq!(
+                term_condition("pri\\ ce:[a\\]b TO \\*]".into(), None),
+                Condition::Range {
+                    target: "pri ce".into(),
+                    lower: Some("a]b".into()),
+                    upper: Some("*".into()),
+                    inclusive_lower: true,
+                    inclusive_upper: true,
+                }
+            );
+            vec![
+                "[1 TO 2]",
+                "price:[1 to 2]",
+                "price:[1 TO 2]x",
+                "a:b:[1 TO 2]",
+            ]
+            .into_iter()
+            .for_each(|term| {
+                assert!(
+                    !matches!(term_condition(term.into(), None), Condition::Range { .. }),
+                    "{}",
+                    term
+                )
+            })
+        }
+
+        #[test]
+        fn test_term_condition_comparison() {
+            vec![
+                ("price:>100", "price", Comparison::GreaterThan, "100"),
+                (
+                    "price:>=100",
+                    "price",
+                    Comparison::GreaterThanOrEqual,
+                    "100",
+                ),
+                ("stars^2:<3", "stars^2", Comparison::LessThan, "3"),
+                (
+                    "date：＜＝2020-01-01",
+                    "date",
+                    Comparison::LessThanOrEqual,
+                    "2020-01-01",
+                ),
+                ("stars:＞＝3", "stars", Comparison::GreaterThanOrEqual, "3"),
+            ]
+            .into_iter()
+            .for_each(|(term, target, comparison, value)| {
+                assert_eq!(
+                    term_condition(term.into(), None),
+                    Condition::Comparison {
+                        target: target.into(),
+                        comparison,
+                        value: value.into()
+                    },
+                    "{}",
+                    term
+                )
+            });
+            vec![">3", "price:>", "price:>=", "price:=>3", "price:>3 4"]
+                .into_iter()
+                .for_each(|term| {
+                    assert!(
+                        !matches!(
+                            term_condition(term.into(), None),
+                            Condition::Comparison { .. }
+                        ),
+                        "{}",
+                        term
+                    )
+                })
+        }
+
+        #[test]
+        fn test_term_condition_exists() {
+            assert_eq!(
+                term_condition("_exists_:title".into(), None),
+                Condition::Exists {
+                    target: "title".into()
+                }
+            );
+            assert_eq!(
+                exists_condition("has：body", "has"),
+                Some(Condition::Exists {
+                    target: "body".into()
+                })
+            );
+            assert_eq!(
+                term_condition("has:body^2".into(), Some("has")),
+                Condition::Exists {
+                    target: "body^2".into()
+                }
+            );
+            // the escaped name is a target
+            assert_eq!(
+                term_condition("\\has:body".into(), Some("has")),
+                Condition::Targeted("has".into(), Box::new(Condition::Keyword("body".into())))
+            );
+            assert_eq!(
+                term_condition("\\_exists_:body".into(), Some("has")),
+                Condition::Targeted(
+                    "_exists_".into(),
+                    Box::new(Condition::Keyword("body".into()))
+                )
+            );
+            vec![
+                "_exists_:",
+                "_EXISTS_:title",
+                "_exists_:a:b",
+                "title:_exists_",
+            ]
+            .into_iter()
+            .for_each(|term| {
+                assert!(
+                    !matches!(term_condition(term.into(), None), Condition::Exists { .. }),
+                    "{}",
+                    term
+                )
+            })
+        }
+
+        #[test]
+        fn test_term_condition_regex() {
+            assert_eq!(
+                term_condition("/err(or)?\\d+/".into(), None),
+                Condition::Regex("err(or)?\\d+".into())
+            );
+            assert_eq!(
+                term_condition("title:/ru.t/".into(), None),
+                Condition::Targeted("title".into(), Box::new(Condition::Regex("ru.t".into())))
+            );
+            assert_eq!(
+                term_condition("a\\:b:/c:d\\/e^2/^3".into(), None),
+                Condition::Targeted(
+                    "a:b".into(),
+                    Box::new(Condition::Regex("c:d/e^2".into()).boosted(Boost::new(3.0).unwrap()))
+                )
+            );
+            assert_eq!(term_condition("/(/".into(), None), Condition::None);
+            assert!(is_invalid_regex("title:/a[/"));
+            vec!["//", "/a", "a/b/", "/a/b"]
+                .into_iter()
+                .for_each(|term| {
+                    assert!(
+                        !matches!(term_condition(term.into(), None), Condition::Regex(_)),
+                        "{}",
+                        term
+                    )
+                })
+        }
+
+        #[test]
+        fn test_term_condition_boost() {
+            let boosted = |condition: Condition, boost: f32| Condition::Boosted {
+                condition: Box::new(condition),
+                boost: Boost::new(boost).unwrap(),
+            };
+            assert_eq!(
+                term_condition("title:rust^2".into(), None),
+                Condition::Targeted(
+                    "title".into(),
+                    Box::new(boosted(Condition::Keyword("rust".into()), 2.0))
+                )
+            );
+            assert_eq!(
+                term_condition("ru*^0.5".into(), None),
+                boosted(Condition::Prefix("ru".into()), 0.5)
+            );
+            assert_eq!(
+                term_condition("rust~1^3".into(), None),
+                boosted(
+                    Condition::Fuzzy {
+                        term: "rust".into(),
+                        max_edits: 1
+                    },
+                    3.0
+                )
+            );
+            assert_eq!(
+                term_condition("log:/a/^2".into(), None),
+                Condition::Targeted(
+                    "log".into(),
+                    Box::new(boosted(Condition::Regex("a".into()), 2.0))
+                )
+            );
+            assert_eq!(
+                term_condition("rust^NaN".into(), None),
+                Condition::Keyword("rust^NaN".into())
+            );
+            assert_eq!(
+                term_condition("_exists_:title^2".into(), None),
+                Condition::Exists {
+                    target: "title^2".into()
+                }
+            );
+            assert!(is_invalid_regex("/a[/^2"));
+        }
+
+        #[test]
+        fn test_phrase_condition() {
+            assert_eq!(
+                phrase_condition("a b".into(), None),
+                Condition::PhraseKeyword("a b".into())
+            );
+            assert_eq!(
+                phrase_condition("a b".into(), Some("")),
+                Condition::PhraseKeyword("a b".into())
+            );
+            assert_eq!(
+                phrase_condition("a b".into(), Some("0")),
+                Condition::PhraseKeyword("a b".into())
+            );
+            assert_eq!(
+                phrase_condition("a b".into(), Some("3")),
+                Condition::Proximity {
+                    phrase: "a b".into(),
+                    slop: 3
+                }
+            );
+            assert_eq!(
+                phrase_condition("a b".into(), Some("99999999999")),
+                Condition::Proximity {
+                    phrase: "a b".into(),
+                    slop: u32::MAX
+                }
+            );
+        }
+
+        #[test]
+        fn test_term_condition_fuzzy() {
+            vec![
+                ("rust~", "rust", 2),
+                ("rust~1", "rust", 1),
+                ("rust～0", "rust", 0),
+                ("rust~3", "rust", 2),
+                ("rust~999", "rust", 2),
+                ("ru~st~1", "ru~st", 1),
+                ("ru\\*st~", "ru*st", 2),
+            ]
+            .into_iter()
+            .for_each(|(term, expected, max_edits)| {
+                assert_eq!(
+                    term_condition(term.into(), None),
+                    Condition::Fuzzy {
+                        term: expected.into(),
+                        max_edits
+                    },
+                    "{}",
+                    term
+                )
+            });
+            vec!["~", "~1", "rust~a", "rust\\~1"]
+                .into_iter()
+                .for_each(|term| {
+                    assert!(
+                        matches!(term_condition(term.into(), None), Condition::Keyword(_)),
+                        "{}",
+                        term
+                    )
+                })
+        }
+
+        #[test]
+        fn test_is_too_many_edits() {
+            vec![
+                ("rust~3", true),
+                ("title:rust~999^2", true),
+                ("rust~99999999999", true),
+                ("rust~2", false),
+                ("rust~", false),
+                ("/a~3/", false),
+                ("price:>a~3", false),
+            ]
+            .into_iter()
+            .for_each(|(term, expected)| {
+                assert_eq!(
+                    is_too_many_edits(term, &term_condition(term.into(), None)),
+                    expected,
+                    "{}",
+                    term
+                )
+            })
+        }
+    }
+}
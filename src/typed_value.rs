@@ -0,0 +1,235 @@
+use crate::{ConditionOnTarget, Schema, Target, TypedValue, ValueType};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static REGEX_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])(?:[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?)?$")
+        .unwrap()
+});
+
+impl ValueType {
+    // `Text` when the value is none of the others, `2020` is an integer rather than a float
+    pub fn infer(value: &str) -> Self {
+        [
+            ValueType::Bool,
+            ValueType::Integer,
+            ValueType::Float,
+            ValueType::Date,
+        ]
+        .into_iter()
+        .find(|value_type| TypedValue::parse(value, *value_type).is_some())
+        .unwrap_or(ValueType::Text)
+    }
+}
+
+impl TypedValue {
+    // None when the value can't be read as the type
+    pub fn parse(value: &str, value_type: ValueType) -> Option<Self> {
+        match value_type {
+            ValueType::Text => Some(TypedValue::Text(value.into())),
+            ValueType::Integer => value.parse::<i64>().ok().map(TypedValue::Integer),
+            // `NaN` and `inf` are not numbers in the queries
+            ValueType::Float => value
+                .parse::<f64>()
+                .ok()
+                .filter(|float| float.is_finite() && value.contains(|c: char| c.is_ascii_digit()))
+                .map(TypedValue::Float),
+            ValueType::Date => REGEX_DATE
+                .is_match(value)
+                .then(|| TypedValue::Date(value.into())),
+            ValueType::Bool => match value.to_lowercase().as_str() {
+                "true" => Some(TypedValue::Bool(true)),
+                "false" => Some(TypedValue::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            TypedValue::Text(_) => ValueType::Text,
+            TypedValue::Integer(_) => ValueType::Integer,
+            TypedValue::Float(_) => ValueType::Float,
+            TypedValue::Date(_) => ValueType::Date,
+            TypedValue::Bool(_) => ValueType::Bool,
+        }
+    }
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_field(mut self, name: &str, value_type: ValueType) -> Self {
+        self.fields.insert(name.into(), value_type);
+        self
+    }
+
+    pub fn value_type(&self, name: &str) -> Option<ValueType> {
+        self.fields.get(name).copied()
+    }
+
+    // the type in the schema is used when the field is in it, and inferred from the value otherwise
+    pub fn typed_value(&self, target: &Target, value: &str) -> Option<TypedValue> {
+        match self.value_type(target.name()) {
+            Some(value_type) => TypedValue::parse(value, value_type),
+            None => TypedValue::parse(value, ValueType::infer(value)),
+        }
+    }
+}
+
+impl ConditionOnTarget {
+    // the type is inferred from the value without the schema, `year:2020` is an integer
+    pub fn typed_value(&self) -> Option<TypedValue> {
+        self.typed_value_with(&Schema::new())
+    }
+
+    // the value of a targeted keyword or a comparison,
+    // None for the other conditions and the values which can't be read as the type in the schema
+    pub fn typed_value_with(&self, schema: &Schema) -> Option<TypedValue> {
+        match self {
+            ConditionOnTarget::Keyword {
+                condition,
+                target: Some(target),
+            }
+            | ConditionOnTarget::Comparison {
+                value: condition,
+                target: Some(target),
+                ..
+            } => schema.typed_value(target, condition),
+            _ => None,
+        }
+    }
+
+    pub fn typed_bounds(&self) -> Option<(Option<TypedValue>, Option<TypedValue>)> {
+        self.typed_bounds_with(&Schema::new())
+    }
+
+    // the lower and upper bounds of a range, the open ends are None
+    pub fn typed_bounds_with(
+        &self, schema: &Schema,
+    ) -> Option<(Option<TypedValue>, Option<TypedValue>)> {
+        match self {
+            ConditionOnTarget::Range {
+                lower,
+                upper,
+                target: Some(target),
+                ..
+            } => {
+                let bound = |bound: &Option<String>| match bound {
+                    Some(bound) => schema.typed_value(target, bound).map(Some),
+                    None => Some(None),
+                };
+                Some((bound(lower)?, bound(upper)?))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_query_to_condition_on_target;
+
+    fn typed_value(query: &str) -> Option<TypedValue> {
+        parse_query_to_condition_on_target(query)
+            .unwrap()
+            .typed_value()
+    }
+
+    fn typed_value_with(query: &str, schema: &Schema) -> Option<TypedValue> {
+        parse_query_to_condition_on_target(query)
+            .unwrap()
+            .typed_value_with(schema)
+    }
+
+    mod test_infer {
+        use super::*;
+
+        #[test]
+        fn test_infer_value_type() {
+            vec![
+                ("2020", ValueType::Integer),
+                ("-3", ValueType::Integer),
+                ("4.5", ValueType::Float),
+                ("1e3", ValueType::Float),
+                ("NaN", ValueType::Text),
+                ("inf", ValueType::Text),
+                ("2020-01-01", ValueType::Date),
+                ("2020-01-01T12:00:00Z", ValueType::Date),
+                ("2020-13-01", ValueType::Text),
+                ("TRUE", ValueType::Bool),
+                ("rust", ValueType::Text),
+            ]
+            .into_iter()
+            .for_each(|(value, expected)| {
+                assert_eq!(ValueType::infer(value), expected, "{}", value)
+            })
+        }
+    }
+
+    mod test_typed_value {
+        use super::*;
+
+        #[test]
+        fn test_typed_value_inferred() {
+            assert_eq!(typed_value("year:2020"), Some(TypedValue::Integer(2020)));
+            assert_eq!(typed_value("active:true"), Some(TypedValue::Bool(true)));
+            assert_eq!(typed_value("price:>=4.5"), Some(TypedValue::Float(4.5)));
+            assert_eq!(typed_value("2020"), None);
+            assert_eq!(typed_value("title:\"2020\""), None);
+            // the same as the empty schema
+            assert_eq!(
+                typed_value("year:2020"),
+                typed_value_with("year:2020", &Schema::new())
+            );
+        }
+
+        #[test]
+        fn test_typed_value_by_schema() {
+            let schema = Schema::new()
+                .with_field("zip", ValueType::Text)
+                .with_field("year", ValueType::Integer);
+            assert_eq!(
+                typed_value_with("zip:01234", &schema),
+                Some(TypedValue::Text("01234".into()))
+            );
+            assert_eq!(typed_value_with("year:twenty", &schema), None);
+            assert_eq!(typed_value("zip:01234"), Some(TypedValue::Integer(1234)));
+        }
+
+        #[test]
+        fn test_typed_bounds() {
+            let schema = Schema::new().with_field("price", ValueType::Float);
+            assert_eq!(
+                parse_query_to_condition_on_target("price:[10 TO *]")
+                    .unwrap()
+                    .typed_bounds_with(&schema),
+                Some((Some(TypedValue::Float(10.0)), None))
+            );
+            assert_eq!(
+                parse_query_to_condition_on_target("price:[10 TO *]")
+                    .unwrap()
+                    .typed_bounds(),
+                Some((Some(TypedValue::Integer(10)), None))
+            );
+            assert_eq!(
+                parse_query_to_condition_on_target("date:{2020-01-01 TO 2021-01-01}")
+                    .unwrap()
+                    .typed_bounds_with(&schema),
+                Some((
+                    Some(TypedValue::Date("2020-01-01".into())),
+                    Some(TypedValue::Date("2021-01-01".into()))
+                ))
+            );
+            assert_eq!(
+                parse_query_to_condition_on_target("price:[ten TO 20]")
+                    .unwrap()
+                    .typed_bounds_with(&schema),
+                None
+            );
+        }
+    }
+}